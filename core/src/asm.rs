@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+
+use crate::{
+    emu::{MEMORY_SIZE, ROM_OFFSET},
+    error::AsmError,
+    instruction::Instruction,
+};
+
+/// Instructions and directives understood by the assembler
+const MNEMONICS: &[&str] = &[
+    "add", "and", "audio", "bad", "call", "cls", "dat", "db", "drw", "exit", "high", "jmp", "jp",
+    "ld", "low", "or", "pitch", "plane", "ret", "rnd", "scd", "scl", "scr", "scu", "se", "shl",
+    "shr", "skp", "sknp", "sne", "sub", "subn", "sys", "xor",
+];
+
+/// Source line split into its parts
+struct Line<'a> {
+    /// Line number, counted from 1
+    number: usize,
+    labels: Vec<&'a str>,
+    /// Mnemonic and operands
+    statement: Option<(&'a str, Vec<&'a str>)>,
+    /// Opcode and address from a `; 0xOPCD @ 0xADDR` annotation
+    annotation: Option<(Option<u16>, usize)>,
+}
+
+/// Assembles source in the syntax of `disasm` listings (and `compiler/asm.ts`) into a ROM.
+///
+/// Each line holds an optional `label:`, an instruction or data directive (`dat` words,
+/// `db` bytes) and an optional `;` comment. Mnemonics and register names are case
+/// insensitive, numbers may be decimal, `0x` hex or `0b` binary, optionally prefixed by
+/// `#`. Addresses may be given by label.
+///
+/// Lines annotated like listings, `; 0xOPCD @ 0xADDR`, are placed at ADDR, so listings
+/// with reordered functions assemble back into the original ROM. `drw` without a height
+/// takes it from the annotated opcode.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(idx, text)| split_line(idx + 1, text))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // First pass: find address of every statement and label
+    let mut labels = HashMap::new();
+    let mut pending = Vec::new();
+    let mut placed = Vec::new();
+    let mut addr = ROM_OFFSET;
+
+    for line in &lines {
+        pending.extend(line.labels.iter().map(|label| (*label, line.number)));
+
+        let (mnemonic, operands) = match &line.statement {
+            Some(statement) => statement,
+            None => continue,
+        };
+
+        if let Some((_, at)) = line.annotation {
+            addr = at;
+        }
+
+        for (label, number) in pending.drain(..) {
+            if labels.insert(label, addr as u16).is_some() {
+                return Err(error(number, format!("duplicate label '{}'", label)));
+            }
+        }
+
+        let size = Encoder { labels: None }
+            .encode(mnemonic, operands, line.annotation.and_then(|(op, _)| op))
+            .map_err(|message| error(line.number, message))?
+            .len();
+        placed.push((line, addr));
+        addr += size;
+    }
+
+    for (label, number) in pending {
+        if labels.insert(label, addr as u16).is_some() {
+            return Err(error(number, format!("duplicate label '{}'", label)));
+        }
+    }
+
+    // Second pass: encode statements, now that all labels are known
+    let encoder = Encoder {
+        labels: Some(&labels),
+    };
+    let mut rom = Vec::new();
+    let mut written = Vec::new();
+
+    for (line, addr) in placed {
+        let (mnemonic, operands) = line.statement.as_ref().unwrap();
+        let bytes = encoder
+            .encode(mnemonic, operands, line.annotation.and_then(|(op, _)| op))
+            .map_err(|message| error(line.number, message))?;
+
+        if addr < ROM_OFFSET || addr + bytes.len() > MEMORY_SIZE {
+            return Err(error(
+                line.number,
+                format!("address {:#06x} is outside of ROM", addr),
+            ));
+        }
+
+        let start = addr - ROM_OFFSET;
+        let end = start + bytes.len();
+        if rom.len() < end {
+            rom.resize(end, 0);
+            written.resize(end, false);
+        }
+
+        if written[start..end].iter().any(|byte| *byte) {
+            return Err(error(
+                line.number,
+                format!("{:#06x} overlaps code or data placed before", addr),
+            ));
+        }
+
+        rom[start..end].copy_from_slice(&bytes);
+        written[start..end].iter_mut().for_each(|byte| *byte = true);
+    }
+
+    Ok(rom)
+}
+
+fn error(line: usize, message: String) -> AsmError {
+    AsmError { line, message }
+}
+
+/// Splits line into labels, statement and annotation
+fn split_line(number: usize, text: &str) -> Result<Line<'_>, AsmError> {
+    let (mut code, comment) = match text.find(';') {
+        Some(idx) => (&text[..idx], Some(&text[idx + 1..])),
+        None => (text, None),
+    };
+
+    let mut labels = Vec::new();
+    while let Some(idx) = code.find(':') {
+        let label = code[..idx].trim();
+        if !is_identifier(label) {
+            return Err(error(number, format!("invalid label '{}'", label)));
+        }
+
+        labels.push(label);
+        code = &code[idx + 1..];
+    }
+
+    let code = code.trim();
+    let statement = if code.is_empty() {
+        None
+    } else {
+        let (mnemonic, rest) = match code.find(char::is_whitespace) {
+            Some(idx) => (&code[..idx], code[idx..].trim()),
+            None => (code, ""),
+        };
+        let operands = if rest.is_empty() {
+            Vec::new()
+        } else {
+            rest.split(',').map(str::trim).collect()
+        };
+
+        Some((mnemonic, operands))
+    };
+
+    Ok(Line {
+        number,
+        labels,
+        statement,
+        annotation: comment.and_then(parse_annotation),
+    })
+}
+
+/// Parses `0xOPCD @ 0xADDR` (opcode is optional), any other comment gives None
+fn parse_annotation(comment: &str) -> Option<(Option<u16>, usize)> {
+    let (op, addr) = match comment.split_once('@') {
+        Some((op, addr)) => (op.trim(), addr.trim()),
+        None => return None,
+    };
+
+    let op = if op.is_empty() {
+        None
+    } else {
+        Some(parse_number(op).filter(|op| *op <= 0xFFFF)? as u16)
+    };
+    let addr = parse_number(addr)? as usize;
+
+    Some((op, addr))
+}
+
+/// Parses decimal, `0x` hex or `0b` binary number, optionally prefixed by `#`
+fn parse_number(text: &str) -> Option<u32> {
+    let text = text.strip_prefix('#').unwrap_or(text);
+    let lower = text.to_ascii_lowercase();
+
+    if let Some(hex) = lower.strip_prefix("0x") {
+        u32::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = lower.strip_prefix("0b") {
+        u32::from_str_radix(bin, 2).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Checks operand against a keyword like `I` or `DT`, ignoring case
+fn is(operand: &str, keyword: &str) -> bool {
+    operand.eq_ignore_ascii_case(keyword)
+}
+
+/// Parses register name, `V0` to `VF`
+fn register(operand: &str) -> Option<u8> {
+    match operand.as_bytes() {
+        [b'v' | b'V', digit] => (*digit as char).to_digit(16).map(|x| x as u8),
+        _ => None,
+    }
+}
+
+/// Encodes statements, resolving labels once they are known
+struct Encoder<'a> {
+    /// Addresses of labels, None in the first pass where every label resolves to 0
+    labels: Option<&'a HashMap<&'a str, u16>>,
+}
+
+impl<'a> Encoder<'a> {
+    /// Encodes statement into bytes, `annotated` is the opcode from its annotation
+    fn encode(
+        &self,
+        mnemonic: &str,
+        operands: &[&str],
+        annotated: Option<u16>,
+    ) -> Result<Vec<u8>, String> {
+        let mnemonic = mnemonic.to_ascii_lowercase();
+
+        let inst = match (mnemonic.as_str(), operands) {
+            ("dat", [_, ..]) => {
+                let mut bytes = Vec::with_capacity(operands.len() * 2);
+                for word in operands {
+                    bytes.extend_from_slice(&self.number(word, 0xFFFF)?.to_be_bytes());
+                }
+                return Ok(bytes);
+            }
+            ("db", [_, ..]) => {
+                return operands
+                    .iter()
+                    .map(|byte| self.number(byte, 0xFF).map(|byte| byte as u8))
+                    .collect();
+            }
+            // Older listings append the address, `bad 0x1234 @ 0x0206`
+            ("bad", [op]) => {
+                let op = op.split('@').next().unwrap_or_default().trim();
+                Instruction::Unknown(self.number(op, 0xFFFF)?)
+            }
+
+            ("cls", []) => Instruction::Cls,
+            ("ret", []) => Instruction::Ret,
+            ("scd", [n]) => Instruction::ScrollDown(self.nibble(n)?),
+            ("scu", [n]) => Instruction::ScrollUp(self.nibble(n)?),
+            ("scr", []) => Instruction::ScrollRight,
+            ("scl", []) => Instruction::ScrollLeft,
+            ("exit", []) => Instruction::Exit,
+            ("low", []) => Instruction::Low,
+            ("high", []) => Instruction::High,
+            ("audio", []) => Instruction::Audio,
+            ("sys", [addr]) => Instruction::Sys(self.addr(addr, 0xFFF)?),
+            ("call", [addr]) => Instruction::Call(self.addr(addr, 0xFFF)?),
+            ("jmp" | "jp", [target]) => match target.split_once('+') {
+                Some((addr, reg)) if register(reg.trim()) == Some(0) => {
+                    Instruction::JumpV0(self.addr(addr.trim(), 0xFFF)?)
+                }
+                Some(_) => return Err(String::from("only V0 can be added to a jump address")),
+                None => Instruction::Jump(self.addr(target, 0xFFF)?),
+            },
+            ("jmp" | "jp", [reg, addr]) if register(reg) == Some(0) => {
+                Instruction::JumpV0(self.addr(addr, 0xFFF)?)
+            }
+
+            ("se", [x, y]) => match register(y) {
+                Some(y) => Instruction::SkipEq(self.register(x)?, y),
+                None => Instruction::SkipEqByte(self.register(x)?, self.byte(y)?),
+            },
+            ("sne", [x, y]) => match register(y) {
+                Some(y) => Instruction::SkipNe(self.register(x)?, y),
+                None => Instruction::SkipNeByte(self.register(x)?, self.byte(y)?),
+            },
+            ("skp", [x]) => Instruction::SkipKey(self.register(x)?),
+            ("sknp", [x]) => Instruction::SkipNotKey(self.register(x)?),
+
+            ("add", [i, x]) if is(i, "I") => Instruction::AddI(self.register(x)?),
+            ("add", [x, y]) => match register(y) {
+                Some(y) => Instruction::Add(self.register(x)?, y),
+                None => Instruction::AddByte(self.register(x)?, self.byte(y)?),
+            },
+            ("or", [x, y]) => Instruction::Or(self.register(x)?, self.register(y)?),
+            ("and", [x, y]) => Instruction::And(self.register(x)?, self.register(y)?),
+            ("xor", [x, y]) => Instruction::Xor(self.register(x)?, self.register(y)?),
+            ("sub", [x, y]) => Instruction::Sub(self.register(x)?, self.register(y)?),
+            ("subn", [x, y]) => Instruction::Subn(self.register(x)?, self.register(y)?),
+            ("shr", [x]) => Instruction::Shr(self.register(x)?, self.register(x)?),
+            ("shr", [x, y]) => Instruction::Shr(self.register(x)?, self.register(y)?),
+            ("shl", [x]) => Instruction::Shl(self.register(x)?, self.register(x)?),
+            ("shl", [x, y]) => Instruction::Shl(self.register(x)?, self.register(y)?),
+            ("rnd", [x, kk]) => Instruction::Random(self.register(x)?, self.byte(kk)?),
+
+            ("drw", [x, y, n]) => {
+                Instruction::Draw(self.register(x)?, self.register(y)?, self.nibble(n)?)
+            }
+            ("drw", [x, y]) => match annotated {
+                Some(op) if op & 0xF000 == 0xD000 => {
+                    Instruction::Draw(self.register(x)?, self.register(y)?, (op & 0xF) as u8)
+                }
+                _ => return Err(String::from("missing sprite height, e.g. `drw V0, V1, #5`")),
+            },
+
+            ("plane", [n]) => Instruction::Plane(self.nibble(n)?),
+            ("pitch", [x]) => Instruction::Pitch(self.register(x)?),
+
+            ("ld", [dst, src]) => return self.load(dst, src),
+
+            (mnemonic, _) if MNEMONICS.contains(&mnemonic) => {
+                return Err(format!("invalid operands for '{}'", mnemonic))
+            }
+            (mnemonic, _) => return Err(format!("unknown instruction '{}'", mnemonic)),
+        };
+
+        Ok(inst.encode().to_be_bytes().to_vec())
+    }
+
+    /// Encodes the many forms of `ld`
+    fn load(&self, dst: &str, src: &str) -> Result<Vec<u8>, String> {
+        let i = |operand: &str| is(operand, "I") || is(operand, "[I]");
+
+        let inst = if i(dst) {
+            let lower = src.to_ascii_lowercase();
+
+            if lower == "long" {
+                Instruction::LoadLongI
+            } else if let Some(addr) = lower.strip_prefix("long ") {
+                let addr = self.addr(src[src.len() - addr.len()..].trim(), 0xFFFF)?;
+                let mut bytes = Instruction::LoadLongI.encode().to_be_bytes().to_vec();
+                bytes.extend_from_slice(&addr.to_be_bytes());
+                return Ok(bytes);
+            } else if let Some(x) = lower.strip_suffix(".hf") {
+                Instruction::BigFont(self.register(x)?)
+            } else if let Some(x) = lower.strip_suffix(".f") {
+                Instruction::Font(self.register(x)?)
+            } else if let Some(x) = lower.strip_suffix(".b") {
+                Instruction::Bcd(self.register(x)?)
+            } else if let Some((x, y)) = src.split_once('-') {
+                Instruction::StoreRange(self.register(x.trim())?, self.register(y.trim())?)
+            } else if let Some(x) = register(src) {
+                Instruction::StoreRegs(x)
+            } else {
+                Instruction::LoadI(self.addr(src, 0xFFF)?)
+            }
+        } else if is(dst, "DT") {
+            Instruction::SetDelay(self.register(src)?)
+        } else if is(dst, "ST") {
+            Instruction::SetSound(self.register(src)?)
+        } else if is(dst, "R") {
+            Instruction::StoreFlags(self.register(src)?)
+        } else if is(dst, "F") {
+            Instruction::Font(self.register(src)?)
+        } else if is(dst, "HF") {
+            Instruction::BigFont(self.register(src)?)
+        } else if is(dst, "B") {
+            Instruction::Bcd(self.register(src)?)
+        } else if let Some((x, y)) = dst.split_once('-') {
+            if !i(src) {
+                return Err(format!("expected I, found '{}'", src));
+            }
+            Instruction::LoadRange(self.register(x.trim())?, self.register(y.trim())?)
+        } else {
+            let x = self.register(dst)?;
+
+            if is(src, "DT") {
+                Instruction::LoadDelay(x)
+            } else if is(src, "K") {
+                Instruction::WaitKey(x)
+            } else if is(src, "R") {
+                Instruction::LoadFlags(x)
+            } else if i(src) {
+                Instruction::LoadRegs(x)
+            } else if let Some(y) = register(src) {
+                Instruction::Load(x, y)
+            } else {
+                Instruction::LoadByte(x, self.byte(src)?)
+            }
+        };
+
+        Ok(inst.encode().to_be_bytes().to_vec())
+    }
+
+    fn register(&self, operand: &str) -> Result<u8, String> {
+        register(operand).ok_or_else(|| format!("expected register, found '{}'", operand))
+    }
+
+    fn byte(&self, operand: &str) -> Result<u8, String> {
+        self.number(operand, 0xFF).map(|byte| byte as u8)
+    }
+
+    fn nibble(&self, operand: &str) -> Result<u8, String> {
+        self.number(operand, 0xF).map(|nibble| nibble as u8)
+    }
+
+    /// Parses number, making sure it is at most `max`
+    fn number(&self, operand: &str, max: u16) -> Result<u16, String> {
+        match parse_number(operand) {
+            Some(value) if value <= max as u32 => Ok(value as u16),
+            Some(value) => Err(format!("{} is out of range (max {})", value, max)),
+            None => Err(format!("expected number, found '{}'", operand)),
+        }
+    }
+
+    /// Parses address, given as number or label
+    fn addr(&self, operand: &str, max: u16) -> Result<u16, String> {
+        if !is_identifier(operand) {
+            return self.number(operand, max);
+        }
+
+        let addr = match self.labels {
+            Some(labels) => *labels
+                .get(operand)
+                .ok_or_else(|| format!("unknown label '{}'", operand))?,
+            None => 0,
+        };
+
+        if addr > max {
+            return Err(format!(
+                "label '{}' at {:#06x} is out of range (max {:#06x})",
+                operand, addr, max
+            ));
+        }
+        Ok(addr)
+    }
+}
@@ -0,0 +1,259 @@
+use std::{
+    f32::consts::PI,
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+    time::Duration,
+};
+
+use crate::emu::{Emulator, DEFAULT_PITCH};
+
+/// Destination of synthesized audio, e.g. a sound card or a file
+pub trait AudioSink {
+    /// Sample rate the sink expects, in Hz
+    fn sample_rate(&self) -> u32;
+
+    /// Receives next mono samples, in range -1.0..=1.0
+    fn write(&mut self, samples: &[f32]) -> io::Result<()>;
+
+    /// Makes sure everything written so far is stored/played
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Sink that discards all audio, for running without sound
+pub struct NullSink {
+    sample_rate: u32,
+}
+
+impl NullSink {
+    pub fn new(sample_rate: u32) -> Self {
+        Self { sample_rate }
+    }
+}
+
+impl AudioSink for NullSink {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn write(&mut self, _samples: &[f32]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Sink that records audio into a 16-bit mono PCM WAV file, or anything else seekable
+pub struct WavSink<W: Write + Seek = File> {
+    writer: BufWriter<W>,
+    sample_rate: u32,
+    /// Number of samples written so far
+    samples: u32,
+}
+
+impl WavSink {
+    /// Creates (or truncates) WAV file at given path
+    pub fn create<P: AsRef<Path>>(path: P, sample_rate: u32) -> io::Result<Self> {
+        Self::new(File::create(path)?, sample_rate)
+    }
+}
+
+impl<W: Write + Seek> WavSink<W> {
+    /// Size of the RIFF/fmt/data headers preceding samples
+    const HEADER_SIZE: u32 = 44;
+
+    /// Writes WAV data into given writer, e.g. an in-memory buffer
+    pub fn new(writer: W, sample_rate: u32) -> io::Result<Self> {
+        let mut sink = Self {
+            writer: BufWriter::new(writer),
+            sample_rate,
+            samples: 0,
+        };
+
+        sink.write_header()?;
+        Ok(sink)
+    }
+
+    /// Writes headers, sizes are those of samples written so far
+    fn write_header(&mut self) -> io::Result<()> {
+        let data_size = self.samples * 2;
+        let w = &mut self.writer;
+
+        w.write_all(b"RIFF")?;
+        w.write_all(&(Self::HEADER_SIZE - 8 + data_size).to_le_bytes())?;
+        w.write_all(b"WAVE")?;
+        w.write_all(b"fmt ")?;
+        w.write_all(&16u32.to_le_bytes())?;
+        // PCM, mono
+        w.write_all(&1u16.to_le_bytes())?;
+        w.write_all(&1u16.to_le_bytes())?;
+        w.write_all(&self.sample_rate.to_le_bytes())?;
+        // Byte rate, block align, bits per sample
+        w.write_all(&(self.sample_rate * 2).to_le_bytes())?;
+        w.write_all(&2u16.to_le_bytes())?;
+        w.write_all(&16u16.to_le_bytes())?;
+        w.write_all(b"data")?;
+        w.write_all(&data_size.to_le_bytes())?;
+
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> AudioSink for WavSink<W> {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn write(&mut self, samples: &[f32]) -> io::Result<()> {
+        for sample in samples {
+            let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            self.writer.write_all(&sample.to_le_bytes())?;
+        }
+
+        self.samples += samples.len() as u32;
+        Ok(())
+    }
+
+    /// Updates sizes in the headers, so that the file is valid up to this point
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.write_header()?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()
+    }
+}
+
+impl<W: Write + Seek> Drop for WavSink<W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Shape of the beep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    Triangle,
+    Sawtooth,
+    Sine,
+}
+
+impl Waveform {
+    /// Names accepted by `from_name`
+    pub const NAMES: &'static [&'static str] = &["square", "triangle", "sawtooth", "sine"];
+
+    /// Gets waveform by its name, see `NAMES`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "square" => Some(Waveform::Square),
+            "triangle" => Some(Waveform::Triangle),
+            "sawtooth" => Some(Waveform::Sawtooth),
+            "sine" => Some(Waveform::Sine),
+            _ => None,
+        }
+    }
+
+    /// Value of the wave at given phase (0.0..1.0)
+    fn sample(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Triangle => 4.0 * (phase - 0.5).abs() - 1.0,
+            Waveform::Sawtooth => 2.0 * phase - 1.0,
+            Waveform::Sine => (2.0 * PI * phase).sin(),
+        }
+    }
+}
+
+/// Synthesizes the beep played while the sound timer is active.
+///
+/// Plays a tone of given waveform, or the XO-CHIP audio pattern at `Emulator::pitch`
+/// once a program loaded a non-silent one with `F002`.
+#[derive(Debug, Clone)]
+pub struct Beeper {
+    /// Frequency of the tone, in Hz
+    pub frequency: f32,
+    /// Volume, from 0.0 (silent) to 1.0
+    pub volume: f32,
+    /// Shape of the tone
+    pub waveform: Waveform,
+    /// Position within current period of the tone or within the pattern (0.0..1.0)
+    phase: f32,
+    /// Fraction of a sample not yet generated by `play`
+    pending: f64,
+}
+
+impl Beeper {
+    pub fn new(frequency: f32, volume: f32, waveform: Waveform) -> Self {
+        Self {
+            frequency,
+            volume,
+            waveform,
+            phase: 0.0,
+            pending: 0.0,
+        }
+    }
+
+    /// Fills `out` with samples for current state of the Emulator
+    pub fn generate(&mut self, emu: &Emulator, sample_rate: u32, out: &mut [f32]) {
+        if emu.st == 0 {
+            out.fill(0.0);
+            self.phase = 0.0;
+            return;
+        }
+
+        let pattern = emu.audio_pattern.iter().any(|byte| *byte != 0);
+        let step = if pattern {
+            // Pattern is 128 bits long, played at `4000 * 2^((pitch - 64) / 48)` bits per second
+            let rate = 4000.0 * 2f32.powf((emu.pitch as f32 - DEFAULT_PITCH as f32) / 48.0);
+            rate / 128.0 / sample_rate as f32
+        } else {
+            self.frequency / sample_rate as f32
+        };
+
+        for sample in out.iter_mut() {
+            let value = if pattern {
+                let bit = (self.phase * 128.0) as usize % 128;
+                if emu.audio_pattern[bit / 8] & (0x80 >> (bit % 8)) != 0 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            } else {
+                self.waveform.sample(self.phase)
+            };
+
+            *sample = value * self.volume;
+            self.phase = (self.phase + step).fract();
+        }
+    }
+
+    /// Generates audio for given amount of emulated time and writes it to the sink
+    pub fn play(
+        &mut self,
+        emu: &Emulator,
+        duration: Duration,
+        sink: &mut dyn AudioSink,
+    ) -> io::Result<()> {
+        let sample_rate = sink.sample_rate();
+
+        self.pending += duration.as_secs_f64() * sample_rate as f64;
+        let len = self.pending as usize;
+        self.pending -= len as f64;
+
+        let mut samples = vec![0.0; len];
+        self.generate(emu, sample_rate, &mut samples);
+        sink.write(&samples)
+    }
+}
+
+impl Default for Beeper {
+    fn default() -> Self {
+        Self::new(440.0, 0.25, Waveform::Square)
+    }
+}
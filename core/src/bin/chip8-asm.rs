@@ -0,0 +1,40 @@
+use std::{env, fs, process};
+
+use chip8_core::asm;
+
+/// Usage text printed for `--help` and on invalid arguments
+const USAGE: &str = "\
+Usage: chip8-asm <SOURCE> <ROM>
+
+Assembles SOURCE, in the syntax of chip8-disasm listings, into ROM.";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let (source, output) = match &args[..] {
+        [flag] if flag == "-h" || flag == "--help" => {
+            println!("{}", USAGE);
+            return;
+        }
+        [source, output] => (source, output),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let text = fs::read_to_string(source).unwrap_or_else(|err| {
+        eprintln!("error: could not read '{}': {}", source, err);
+        process::exit(1);
+    });
+
+    let rom = asm::assemble(&text).unwrap_or_else(|err| {
+        eprintln!("error: {}: {}", source, err);
+        process::exit(1);
+    });
+
+    if let Err(err) = fs::write(output, rom) {
+        eprintln!("error: could not write '{}': {}", output, err);
+        process::exit(1);
+    }
+}
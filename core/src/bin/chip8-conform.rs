@@ -0,0 +1,68 @@
+use std::{env, fs, path::Path, process};
+
+use chip8_core::conformance::{self, Suite};
+
+/// Usage text printed for `--help` and on invalid arguments
+const USAGE: &str = "\
+Usage: chip8-conform <SUITE>
+
+Runs the test ROMs listed in SUITE and reports the verdicts they show on screen. ROM
+paths are relative to the suite file. Exits with status 1 if any test fails.";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let path = match &args[..] {
+        [flag] if flag == "-h" || flag == "--help" => {
+            println!("{}", USAGE);
+            return;
+        }
+        [path] => Path::new(path),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let suite = fs::read_to_string(path)
+        .map_err(|err| format!("could not read suite '{}': {}", path.display(), err))
+        .and_then(|text| Suite::parse(&text).map_err(|err| format!("{}: {}", path.display(), err)))
+        .unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        });
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let (mut passed, mut failed) = (0, 0);
+    let mut ok = true;
+
+    for test in &suite.tests {
+        let rom_path = dir.join(&test.rom);
+        let rom = fs::read(&rom_path).unwrap_or_else(|err| {
+            eprintln!(
+                "error: could not read ROM '{}': {}",
+                rom_path.display(),
+                err
+            );
+            process::exit(1);
+        });
+
+        let report = conformance::run(&suite, test, &rom);
+        print!("{}", report);
+
+        let (pass, fail) = report.counts();
+        passed += pass;
+        failed += fail;
+        ok &= report.passed();
+    }
+
+    println!(
+        "{} tests, {} verdicts passed, {} failed",
+        suite.tests.len(),
+        passed,
+        failed
+    );
+    if !ok {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,42 @@
+use std::{env, fs, process};
+
+use chip8_core::disasm;
+
+/// Usage text printed for `--help` and on invalid arguments
+const USAGE: &str = "\
+Usage: chip8-disasm <ROM> [OUTPUT]
+
+Disassembles ROM into a listing, written to OUTPUT or printed.";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let (rom, output) = match &args[..] {
+        [flag] if flag == "-h" || flag == "--help" => {
+            println!("{}", USAGE);
+            return;
+        }
+        [rom] => (rom, None),
+        [rom, output] => (rom, Some(output)),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let data = fs::read(rom).unwrap_or_else(|err| {
+        eprintln!("error: could not read ROM '{}': {}", rom, err);
+        process::exit(1);
+    });
+    let listing = disasm::disassemble(&data);
+
+    match output {
+        Some(path) => {
+            if let Err(err) = fs::write(path, listing) {
+                eprintln!("error: could not write '{}': {}", path, err);
+                process::exit(1);
+            }
+        }
+        None => print!("{}", listing),
+    }
+}
@@ -0,0 +1,68 @@
+/// Rate at which delay and sound timers are decremented
+pub const TIMER_HZ: u64 = 60;
+/// Default number of instructions executed per second (10 per frame)
+pub const DEFAULT_IPS: u32 = 600;
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// Emulated time, decides when instructions and timer updates are due.
+///
+/// All times are nanoseconds since the Emulator started. Timer updates are derived
+/// from their count, so they never drift. Instructions are spaced `1 / ips` seconds
+/// apart, carrying over the fraction of a nanosecond that does not divide evenly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Clock {
+    /// Current emulated time
+    pub now: u64,
+    /// Number of timer updates done so far
+    pub timer_ticks: u64,
+    /// Time next instruction is due at
+    pub next_instruction: u64,
+    /// Fraction of a nanosecond (in `1 / ips` units) carried over to the next instruction
+    pub remainder: u64,
+}
+
+impl Clock {
+    /// Time next timer update is due at
+    pub fn next_timer(&self) -> u64 {
+        Self::timer_at(self.timer_ticks + 1)
+    }
+
+    /// Time given timer update (counted from 1) happens at, the start of that frame
+    pub fn timer_at(ticks: u64) -> u64 {
+        ticks * NANOS_PER_SEC / TIMER_HZ
+    }
+
+    /// Whether `now` lies between the last timer update and the next one, as it always does
+    /// while running, without the times overflowing
+    pub fn is_valid(&self) -> bool {
+        let next = self.timer_ticks.checked_add(1);
+        if next
+            .and_then(|next| next.checked_mul(NANOS_PER_SEC))
+            .is_none()
+        {
+            return false;
+        }
+
+        Self::timer_at(self.timer_ticks) <= self.now && self.now < self.next_timer()
+    }
+
+    /// Schedules next instruction, `1 / ips` seconds after the current one
+    pub fn advance_instruction(&mut self, ips: u32) {
+        let ips = ips.max(1) as u64;
+
+        self.next_instruction += NANOS_PER_SEC / ips;
+        self.remainder += NANOS_PER_SEC % ips;
+
+        if self.remainder >= ips {
+            self.remainder -= ips;
+            self.next_instruction += 1;
+        }
+    }
+
+    /// Marks the next timer update as done
+    pub fn advance_timer(&mut self) {
+        self.now = self.next_timer();
+        self.timer_ticks += 1;
+    }
+}
@@ -0,0 +1,448 @@
+use std::{convert::TryFrom, fmt};
+
+use crate::{
+    emu::{Emulator, StepOutcome},
+    headless::InputScript,
+    image,
+    quirks::Quirks,
+};
+
+/// Tick drawn by the Timendus test suite ROMs next to a passing test, see `Glyph`
+const PASS: &str = "\
+......#
+.....#.
+#...#..
+.#.#...
+..#....";
+
+/// Cross drawn by the Timendus test suite ROMs next to a failing test, see `Glyph`
+const FAIL: &str = "\
+#...#
+.#.#.
+..#..
+.#.#.
+#...#";
+
+/// Small picture searched for on the display, e.g. a tick or a cross.
+///
+/// Written as rows of `#` (lit) and `.` (dark). The whole rectangle has to match, so a
+/// glyph is not found inside bigger lit areas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glyph {
+    rows: Vec<Vec<bool>>,
+}
+
+impl Glyph {
+    /// Parses glyph rows, which must all be the same width
+    pub fn parse<'a, I: IntoIterator<Item = &'a str>>(rows: I) -> Result<Self, String> {
+        let mut parsed: Vec<Vec<bool>> = Vec::new();
+
+        for row in rows {
+            let row = row.trim();
+            if !row.chars().all(|c| c == '#' || c == '.') {
+                return Err(format!("glyph row '{}' may only contain '#' and '.'", row));
+            }
+            parsed.push(row.chars().map(|c| c == '#').collect());
+        }
+
+        let width = parsed.first().map_or(0, Vec::len);
+        if width == 0 || parsed.iter().any(|row| row.len() != width) {
+            return Err(String::from(
+                "glyph rows must be non-empty and equally wide",
+            ));
+        }
+        if !parsed.iter().flatten().any(|lit| *lit) {
+            return Err(String::from("glyph has no lit pixels"));
+        }
+
+        Ok(Self { rows: parsed })
+    }
+
+    pub fn width(&self) -> usize {
+        self.rows[0].len()
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether the glyph is drawn with its top left corner at (x, y)
+    fn is_at(&self, emu: &Emulator, x: usize, y: usize) -> bool {
+        let cols = emu.width();
+
+        self.rows.iter().enumerate().all(|(dy, row)| {
+            row.iter()
+                .enumerate()
+                .all(|(dx, lit)| (emu.display[(y + dy) * cols + x + dx] != 0) == *lit)
+        })
+    }
+}
+
+/// Result shown by a test ROM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Pass,
+    Fail,
+}
+
+/// Verdict glyph found on the display, top left corner at (x, y)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mark {
+    pub verdict: Verdict,
+    pub x: usize,
+    pub y: usize,
+}
+
+/// Finds all pass and fail glyphs on the display, in reading order: lines from top to
+/// bottom, each from left to right. Glyphs overlapping vertically are on the same line.
+pub fn find_marks(emu: &Emulator, pass: &Glyph, fail: &Glyph) -> Vec<Mark> {
+    let mut found: Vec<(Mark, usize)> = Vec::new();
+
+    for (glyph, verdict) in [(pass, Verdict::Pass), (fail, Verdict::Fail)] {
+        if glyph.width() > emu.width() || glyph.height() > emu.height() {
+            continue;
+        }
+
+        for y in 0..=emu.height() - glyph.height() {
+            for x in 0..=emu.width() - glyph.width() {
+                if glyph.is_at(emu, x, y) {
+                    found.push((Mark { verdict, x, y }, glyph.height()));
+                }
+            }
+        }
+    }
+
+    found.sort_by_key(|(mark, _)| (mark.y, mark.x));
+
+    // Group into lines, then order each line by column
+    let mut lines: Vec<(usize, Vec<Mark>)> = Vec::new();
+    for (mark, height) in found {
+        match lines.last_mut() {
+            Some((bottom, line)) if mark.y < *bottom => {
+                *bottom = (*bottom).max(mark.y + height);
+                line.push(mark);
+            }
+            _ => lines.push((mark.y + height, vec![mark])),
+        }
+    }
+
+    lines
+        .into_iter()
+        .flat_map(|(_, mut line)| {
+            line.sort_by_key(|mark| mark.x);
+            line
+        })
+        .collect()
+}
+
+/// Test ROM to run, and what its verdicts mean
+#[derive(Debug, Clone)]
+pub struct Test {
+    pub name: String,
+    /// Path of the ROM, relative to the suite file
+    pub rom: String,
+    pub quirks: Quirks,
+    /// Frames to run for before reading the display
+    pub frames: u64,
+    /// Bytes written to memory after loading the ROM, e.g. to skip a menu
+    pub pokes: Vec<(u16, u8)>,
+    /// Keys to press, see `InputScript`
+    pub input: String,
+    /// Names of the verdicts in reading order, e.g. opcodes or quirks
+    pub results: Vec<String>,
+}
+
+/// Set of test ROMs with the glyphs they draw.
+///
+/// Suite files have `key = value` lines grouped under `[test NAME]` sections, one per ROM
+/// run. Empty lines and lines starting with `#` are ignored:
+///
+/// ```text
+/// [test quirks]
+/// rom = 5-quirks.ch8
+/// quirks = vip
+/// # Pick CHIP-8 without going through the menu
+/// poke = 0x1FF 1
+/// frames = 600
+/// results = vf-reset memory display-wait clipping shifting jumping
+///
+/// [test getkey]
+/// rom = 6-keypad.ch8
+/// poke = 0x1FF 3
+/// input = 60 down 5
+/// input = 70 up 5
+/// results = FX0A
+/// ```
+///
+/// `poke = ADDR VALUE` writes memory after loading the ROM and `input = FRAME down|up KEY`
+/// presses keys, as with `--input`. `quirks` defaults to `cowgod` and `frames` to 300.
+/// `results` names the verdicts found on screen in reading order, they are numbered if left
+/// out.
+///
+/// The tick and cross drawn by the Timendus test suite are looked for unless replaced with
+/// `[glyph pass]` and `[glyph fail]` sections, holding rows of `#` and `.` (see `Glyph`).
+/// The runner prints the screen when it finds no marks, so other glyphs can be copied from
+/// there. Comments can not be used in these sections, as rows start with `#` too.
+#[derive(Debug, Clone)]
+pub struct Suite {
+    pub pass: Glyph,
+    pub fail: Glyph,
+    pub tests: Vec<Test>,
+}
+
+impl Suite {
+    /// Parses suite file, see `Suite` for the format
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut suite = Suite {
+            pass: Glyph::parse(PASS.lines()).unwrap(),
+            fail: Glyph::parse(FAIL.lines()).unwrap(),
+            tests: Vec::new(),
+        };
+        let mut section = Section::Top;
+        let mut rows: Vec<&str> = Vec::new();
+
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') && !matches!(section, Section::Glyph(_)) {
+                continue;
+            }
+            let err = |msg: String| format!("{} on line {}", msg, idx + 1);
+
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header
+                    .strip_suffix(']')
+                    .ok_or_else(|| err(format!("unterminated section '{}'", line)))?;
+                suite.finish_section(&section, &rows).map_err(err)?;
+                rows.clear();
+                section = suite.start_section(header.trim()).map_err(err)?;
+                continue;
+            }
+
+            let test = match section {
+                Section::Top => {
+                    return Err(err(String::from("expected a section like '[test NAME]'")))
+                }
+                Section::Glyph(_) => {
+                    rows.push(line);
+                    continue;
+                }
+                Section::Test => suite.tests.last_mut().unwrap(),
+            };
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| err(format!("expected 'key = value', found '{}'", line)))?;
+            let (key, value) = (key.trim(), value.trim());
+
+            match key {
+                "rom" => test.rom = value.to_string(),
+                "quirks" => {
+                    test.quirks = Quirks::from_name(value).ok_or_else(|| {
+                        err(format!(
+                            "unknown quirks profile '{}', expected one of: {}",
+                            value,
+                            Quirks::NAMES.join(", ")
+                        ))
+                    })?
+                }
+                "frames" => {
+                    test.frames = value
+                        .parse()
+                        .map_err(|_| err(format!("invalid frame count '{}'", value)))?
+                }
+                "poke" => test.pokes.push(
+                    parse_poke(value).ok_or_else(|| err(format!("invalid poke '{}'", value)))?,
+                ),
+                "input" => {
+                    InputScript::parse(value)
+                        .map_err(|_| err(format!("invalid input '{}'", value)))?;
+                    test.input.push_str(value);
+                    test.input.push('\n');
+                }
+                "results" => test.results = value.split_whitespace().map(String::from).collect(),
+                _ => return Err(err(format!("unknown setting '{}'", key))),
+            }
+        }
+
+        suite
+            .finish_section(&section, &rows)
+            .map_err(|err| format!("{} at end of file", err))?;
+
+        Ok(suite)
+    }
+
+    /// Enters section with given header
+    fn start_section(&mut self, header: &str) -> Result<Section, String> {
+        match header.split_once(char::is_whitespace) {
+            Some(("test", name)) => {
+                self.tests.push(Test {
+                    name: name.trim().to_string(),
+                    rom: String::new(),
+                    quirks: Quirks::default(),
+                    frames: 300,
+                    pokes: Vec::new(),
+                    input: String::new(),
+                    results: Vec::new(),
+                });
+                Ok(Section::Test)
+            }
+            Some(("glyph", "pass")) => Ok(Section::Glyph(Verdict::Pass)),
+            Some(("glyph", "fail")) => Ok(Section::Glyph(Verdict::Fail)),
+            _ => Err(format!("unknown section '[{}]'", header)),
+        }
+    }
+
+    /// Checks the section just ended is complete
+    fn finish_section(&mut self, section: &Section, rows: &[&str]) -> Result<(), String> {
+        match section {
+            Section::Top => {}
+            Section::Test => {
+                let test = self.tests.last().unwrap();
+                if test.rom.is_empty() {
+                    return Err(format!("test '{}' needs a rom", test.name));
+                }
+            }
+            Section::Glyph(verdict) => {
+                let glyph = Glyph::parse(rows.iter().copied())?;
+                match verdict {
+                    Verdict::Pass => self.pass = glyph,
+                    Verdict::Fail => self.fail = glyph,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Part of the suite file being parsed
+enum Section {
+    /// Before any section header
+    Top,
+    /// `[test NAME]`, settings go to the last test
+    Test,
+    /// `[glyph pass]` or `[glyph fail]`
+    Glyph(Verdict),
+}
+
+/// Parses `ADDR VALUE`, both decimal or `0x` hexadecimal
+fn parse_poke(text: &str) -> Option<(u16, u8)> {
+    let number = |text: &str| match text.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    };
+
+    match text.split_whitespace().collect::<Vec<&str>>()[..] {
+        [addr, value] => Some((number(addr)?, u8::try_from(number(value)?).ok()?)),
+        _ => None,
+    }
+}
+
+/// What a test ROM showed after running
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub name: String,
+    /// Verdicts found on screen, named by `Test::results`
+    pub results: Vec<(String, Verdict)>,
+    /// Why the results can not be trusted, e.g. the emulator failed or verdicts are missing
+    pub error: Option<String>,
+    /// Final display as text, for finding out what went wrong
+    pub screen: String,
+}
+
+impl Report {
+    /// Whether every verdict passed and nothing went wrong
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.results.iter().all(|(_, v)| *v == Verdict::Pass)
+    }
+
+    /// Number of passing and failing verdicts
+    pub fn counts(&self) -> (usize, usize) {
+        let passed = self
+            .results
+            .iter()
+            .filter(|(_, verdict)| *verdict == Verdict::Pass)
+            .count();
+        (passed, self.results.len() - passed)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (passed, failed) = self.counts();
+        writeln!(f, "{}: {} passed, {} failed", self.name, passed, failed)?;
+
+        for (name, verdict) in &self.results {
+            let verdict = match verdict {
+                Verdict::Pass => "ok",
+                Verdict::Fail => "FAIL",
+            };
+            writeln!(f, "  {:<4}  {}", verdict, name)?;
+        }
+
+        if let Some(err) = &self.error {
+            writeln!(f, "  error: {}", err)?;
+            for line in self.screen.lines() {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs a test ROM and reads the verdicts off the display
+pub fn run(suite: &Suite, test: &Test, rom: &[u8]) -> Report {
+    let mut emu = Emulator::with_seed(0);
+    emu.quirks = test.quirks;
+    emu.load_sprites();
+
+    let mut error = emu.load_rom(rom).err().map(|err| err.to_string());
+    for &(addr, value) in &test.pokes {
+        emu.memory[addr as usize] = value;
+    }
+    // Checked when parsing the suite
+    let mut script = InputScript::parse(&test.input).unwrap();
+
+    while error.is_none() && emu.clock.timer_ticks < test.frames {
+        script.apply(&mut emu);
+
+        match emu.cycle() {
+            Ok(StepOutcome::Exited) => break,
+            Ok(_) => {}
+            Err(err) => {
+                error = Some(format!("{} at frame {}", err, emu.clock.timer_ticks));
+            }
+        }
+    }
+
+    let marks = find_marks(&emu, &suite.pass, &suite.fail);
+    if error.is_none() && !test.results.is_empty() && marks.len() != test.results.len() {
+        error = Some(format!(
+            "expected {} verdicts, found {}",
+            test.results.len(),
+            marks.len()
+        ));
+    }
+    if error.is_none() && marks.is_empty() {
+        error = Some(String::from("no verdicts found"));
+    }
+
+    let results = marks
+        .iter()
+        .enumerate()
+        .map(|(idx, mark)| {
+            let name = match test.results.get(idx) {
+                Some(name) => name.clone(),
+                None => format!("#{} at ({}, {})", idx + 1, mark.x, mark.y),
+            };
+            (name, mark.verdict)
+        })
+        .collect();
+
+    Report {
+        name: test.name.clone(),
+        results,
+        error,
+        screen: image::ascii(&emu),
+    }
+}
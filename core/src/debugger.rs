@@ -0,0 +1,562 @@
+use std::{fmt, fmt::Write, time::Duration};
+
+use crate::{
+    emu::{Emulator, StepOutcome},
+    error::EmulatorError,
+    headless,
+    instruction::Instruction,
+    watch::{Access, Watchpoint},
+};
+
+/// Help text printed by the `help` command
+pub const HELP: &str = "\
+Commands:
+  c, continue              Run until a breakpoint is hit
+  s, step [N]              Execute N instructions (default 1)
+  n, next                  Step over, running a 2nnn CALL until it returns
+  o, out                   Step out, running until the current routine returns (00EE)
+  b, break ADDR [if COND]  Stop when pc reaches ADDR, and COND holds
+  b, break if COND         Stop before any instruction where COND holds, e.g. V3 == 0x10
+  d, delete N              Remove breakpoint N
+  breaks                   List breakpoints
+  w, watch KIND ADDR [LEN] Stop after an instruction reads, writes or changes LEN bytes
+                           from ADDR (default 1), KIND being read, write or change
+  unwatch N                Remove watchpoint N
+  watches                  List watchpoints
+  r, regs                  Show registers and timers
+  m, mem ADDR [LEN]        Dump LEN bytes of memory from ADDR (default 16)
+  stack                    Show return addresses on the stack
+  dis [ADDR] [COUNT]       Disassemble COUNT instructions from ADDR (default pc 10)
+  h, help                  Show this help
+  q, quit                  Exit the emulator
+
+ADDR is a number (decimal, 0x hex or 0b binary), pc or i. COND compares V0-VF, I,
+DT, ST, SP or PC against a number with ==, !=, <, <=, > or >=.";
+
+/// Value a breakpoint condition looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    V(u8),
+    I,
+    Dt,
+    St,
+    Sp,
+    Pc,
+}
+
+impl Target {
+    fn value(self, emu: &Emulator) -> u16 {
+        match self {
+            Target::V(x) => emu.v[x as usize] as u16,
+            Target::I => emu.i,
+            Target::Dt => emu.dt as u16,
+            Target::St => emu.st as u16,
+            Target::Sp => emu.sp as u16,
+            Target::Pc => emu.pc,
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::V(x) => write!(f, "V{:X}", x),
+            Target::I => write!(f, "I"),
+            Target::Dt => write!(f, "DT"),
+            Target::St => write!(f, "ST"),
+            Target::Sp => write!(f, "SP"),
+            Target::Pc => write!(f, "PC"),
+        }
+    }
+}
+
+/// Comparison operator of a breakpoint condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compare {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Compare {
+    /// Operators as typed, longer ones first so `<=` is not read as `<`
+    const SYMBOLS: [(&'static str, Compare); 6] = [
+        ("==", Compare::Eq),
+        ("!=", Compare::Ne),
+        ("<=", Compare::Le),
+        (">=", Compare::Ge),
+        ("<", Compare::Lt),
+        (">", Compare::Gt),
+    ];
+
+    fn holds(self, left: u16, right: u16) -> bool {
+        match self {
+            Compare::Eq => left == right,
+            Compare::Ne => left != right,
+            Compare::Lt => left < right,
+            Compare::Le => left <= right,
+            Compare::Gt => left > right,
+            Compare::Ge => left >= right,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        Self::SYMBOLS
+            .iter()
+            .find(|(_, cmp)| *cmp == self)
+            .unwrap()
+            .0
+    }
+}
+
+/// Condition on a register, e.g. `V3 == 0x10`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    pub target: Target,
+    pub compare: Compare,
+    pub value: u16,
+}
+
+impl Condition {
+    /// Parses a condition like `V3 == 0x10`, spaces around the operator are optional
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (pos, symbol, compare) = Compare::SYMBOLS
+            .iter()
+            .filter_map(|&(symbol, compare)| Some((text.find(symbol)?, symbol, compare)))
+            .min_by_key(|&(pos, symbol, _)| (pos, usize::MAX - symbol.len()))
+            .ok_or_else(|| format!("missing comparison in '{}'", text))?;
+
+        let name = text[..pos].trim();
+        let target = match name.to_ascii_uppercase().as_str() {
+            "I" => Target::I,
+            "DT" => Target::Dt,
+            "ST" => Target::St,
+            "SP" => Target::Sp,
+            "PC" => Target::Pc,
+            reg => match reg.strip_prefix('V') {
+                Some(x) if x.len() == 1 => Target::V(
+                    u8::from_str_radix(x, 16)
+                        .map_err(|_| format!("unknown register '{}'", name))?,
+                ),
+                _ => return Err(format!("unknown register '{}'", name)),
+            },
+        };
+
+        Ok(Condition {
+            target,
+            compare,
+            value: parse_number(text[pos + symbol.len()..].trim())?,
+        })
+    }
+
+    pub fn holds(&self, emu: &Emulator) -> bool {
+        self.compare.holds(self.target.value(emu), self.value)
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {:#x}",
+            self.target,
+            self.compare.symbol(),
+            self.value
+        )
+    }
+}
+
+/// Stops execution when `pc` reaches `addr` and `condition` holds, either may be left out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub addr: Option<u16>,
+    pub condition: Option<Condition>,
+}
+
+impl Breakpoint {
+    pub fn hit(&self, emu: &Emulator) -> bool {
+        self.addr.is_none_or(|addr| emu.pc == addr)
+            && self.condition.is_none_or(|cond| cond.holds(emu))
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.addr, self.condition) {
+            (Some(addr), Some(cond)) => write!(f, "{:#06x} if {}", addr, cond),
+            (Some(addr), None) => write!(f, "{:#06x}", addr),
+            (None, Some(cond)) => write!(f, "if {}", cond),
+            (None, None) => write!(f, "always"),
+        }
+    }
+}
+
+/// Temporary stop set up by step over and step out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Until {
+    /// `pc` is back at `addr` with the stack at depth `sp`, i.e. the CALL returned
+    Return { addr: u16, sp: u8 },
+    /// Stack is shallower than `sp`, i.e. the current routine returned
+    Out { sp: u8 },
+}
+
+/// Debugger layer over an Emulator, with breakpoints and stepping.
+///
+/// Frontends run the Emulator through `run_for` instead of `Emulator::run_for`, and feed
+/// lines typed by the user to `command`. While `stopped`, `run_for` does nothing.
+#[derive(Debug, Clone, Default)]
+pub struct Debugger {
+    pub breakpoints: Vec<Breakpoint>,
+    /// Whether execution is stopped, waiting for commands
+    pub stopped: bool,
+    until: Option<Until>,
+    /// Instruction slot execution resumed from, which must not stop again right away
+    resumed_at: Option<u64>,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the Emulator for given amount of emulated time, unless stopped.
+    ///
+    /// Returns `StepOutcome::Breakpoint` when stopped, either before or during this call.
+    pub fn run_for(
+        &mut self,
+        emu: &mut Emulator,
+        duration: Duration,
+    ) -> Result<StepOutcome, EmulatorError> {
+        if self.stopped {
+            return Ok(StepOutcome::Breakpoint);
+        }
+
+        let resumed_at = self.resumed_at;
+        let breakpoints = &self.breakpoints;
+        let until = self.until;
+
+        let outcome = emu.run_for_until(duration, |emu| {
+            if resumed_at == Some(emu.clock.next_instruction) {
+                return false;
+            }
+
+            let done = match until {
+                Some(Until::Return { addr, sp }) => emu.pc == addr && emu.sp == sp,
+                Some(Until::Out { sp }) => emu.sp < sp,
+                None => false,
+            };
+
+            done || breakpoints.iter().any(|bp| bp.hit(emu))
+        });
+
+        match outcome {
+            Ok(StepOutcome::Breakpoint) | Ok(StepOutcome::Watchpoint) | Err(_) => self.stop(),
+            _ => {}
+        }
+
+        outcome
+    }
+
+    /// Stops execution, e.g. on an error the user should be able to inspect
+    pub fn stop(&mut self) {
+        self.stopped = true;
+        self.until = None;
+        self.resumed_at = None;
+    }
+
+    /// Describes where execution is stopped, e.g. `stopped at 0x0230: ld V0, #1`, along
+    /// with the watchpoint that stopped it
+    pub fn status(&self, emu: &Emulator) -> String {
+        let inst = match emu.fetch() {
+            Ok(inst) => inst.to_string(),
+            Err(err) => err.to_string(),
+        };
+        let stopped = format!("stopped at {:#06x}: {}", emu.pc, inst);
+
+        match emu.watch_hit {
+            Some(hit) => format!("{}\n{}", hit, stopped),
+            None => stopped,
+        }
+    }
+
+    /// Runs a command typed by the user, returning what to print.
+    ///
+    /// Commands that resume execution only take effect on the next `run_for`. `quit` is
+    /// left to the frontend, as only it knows how to exit.
+    pub fn command(&mut self, emu: &mut Emulator, line: &str) -> Result<String, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (name, args) = match words.split_first() {
+            Some((name, args)) => (name.to_ascii_lowercase(), args),
+            None => return Ok(String::new()),
+        };
+
+        match name.as_str() {
+            "h" | "help" => Ok(HELP.to_string()),
+            "c" | "continue" => {
+                self.resume(emu, None);
+                Ok(String::new())
+            }
+            "s" | "step" => {
+                let count = match args {
+                    [] => 1,
+                    [count] => parse_number(count)?,
+                    _ => return Err(String::from("usage: step [N]")),
+                };
+                self.step(emu, count)
+            }
+            "n" | "next" => match emu.fetch() {
+                Ok(Instruction::Call(_)) => {
+                    let until = Until::Return {
+                        addr: emu.pc.wrapping_add(2),
+                        sp: emu.sp,
+                    };
+                    self.resume(emu, Some(until));
+                    Ok(String::new())
+                }
+                _ => self.step(emu, 1),
+            },
+            "o" | "out" => {
+                if emu.sp == 0 {
+                    return Err(String::from("not inside a routine, stack is empty"));
+                }
+                self.resume(emu, Some(Until::Out { sp: emu.sp }));
+                Ok(String::new())
+            }
+            "b" | "break" => {
+                let rest = line.trim_start()[words[0].len()..].trim();
+                let breakpoint = parse_breakpoint(emu, rest)?;
+                self.breakpoints.push(breakpoint);
+                Ok(format!(
+                    "breakpoint {}: {}",
+                    self.breakpoints.len() - 1,
+                    breakpoint
+                ))
+            }
+            "d" | "delete" => match args {
+                [n] => {
+                    let n = parse_number(n)? as usize;
+                    if n >= self.breakpoints.len() {
+                        return Err(format!("no breakpoint {}", n));
+                    }
+                    let breakpoint = self.breakpoints.remove(n);
+                    Ok(format!("deleted breakpoint {}: {}", n, breakpoint))
+                }
+                _ => Err(String::from("usage: delete N")),
+            },
+            "w" | "watch" => {
+                let (access, addr, len) = match args {
+                    [access, addr] => (access, parse_addr(emu, addr)?, 1),
+                    [access, addr, len] => (access, parse_addr(emu, addr)?, parse_number(len)?),
+                    _ => return Err(String::from("usage: watch read|write|change ADDR [LEN]")),
+                };
+                let access = Access::from_name(&access.to_ascii_lowercase()).ok_or_else(|| {
+                    format!(
+                        "unknown access '{}', expected one of: {}",
+                        access,
+                        Access::NAMES.join(", ")
+                    )
+                })?;
+                if len == 0 {
+                    return Err(String::from("watched range must not be empty"));
+                }
+
+                let watchpoint = Watchpoint {
+                    start: addr,
+                    end: addr.saturating_add(len - 1),
+                    access,
+                };
+                emu.watchpoints.push(watchpoint);
+                Ok(format!(
+                    "watchpoint {}: {}",
+                    emu.watchpoints.len() - 1,
+                    watchpoint
+                ))
+            }
+            "unwatch" => match args {
+                [n] => {
+                    let n = parse_number(n)? as usize;
+                    if n >= emu.watchpoints.len() {
+                        return Err(format!("no watchpoint {}", n));
+                    }
+                    let watchpoint = emu.watchpoints.remove(n);
+                    Ok(format!("deleted watchpoint {}: {}", n, watchpoint))
+                }
+                _ => Err(String::from("usage: unwatch N")),
+            },
+            "watches" => {
+                let mut out = String::new();
+                for (n, watchpoint) in emu.watchpoints.iter().enumerate() {
+                    writeln!(out, "{}: {}", n, watchpoint).unwrap();
+                }
+                Ok(out)
+            }
+            "breaks" => {
+                let mut out = String::new();
+                for (n, bp) in self.breakpoints.iter().enumerate() {
+                    writeln!(out, "{}: {}", n, bp).unwrap();
+                }
+                Ok(out)
+            }
+            "r" | "regs" => Ok(headless::registers(emu)),
+            "m" | "mem" => {
+                let (addr, len) = match args {
+                    [addr] => (parse_addr(emu, addr)?, 16),
+                    [addr, len] => (parse_addr(emu, addr)?, parse_number(len)?),
+                    _ => return Err(String::from("usage: mem ADDR [LEN]")),
+                };
+                Ok(memory(emu, addr, len))
+            }
+            "stack" => {
+                if emu.sp == 0 {
+                    return Ok(String::from("stack is empty"));
+                }
+
+                let mut out = String::new();
+                for (n, addr) in emu.stack[..emu.sp as usize].iter().enumerate().rev() {
+                    writeln!(out, "{:X}: {:#06x}", n, addr).unwrap();
+                }
+                Ok(out)
+            }
+            "dis" => {
+                let (addr, count) = match args {
+                    [] => (emu.pc, 10),
+                    [addr] => (parse_addr(emu, addr)?, 10),
+                    [addr, count] => (parse_addr(emu, addr)?, parse_number(count)?),
+                    _ => return Err(String::from("usage: dis [ADDR] [COUNT]")),
+                };
+                Ok(self.disassemble(emu, addr, count))
+            }
+            _ => Err(format!("unknown command '{}', try help", words[0])),
+        }
+    }
+
+    /// Resumes execution, skipping breakpoints on the instruction stopped at
+    fn resume(&mut self, emu: &Emulator, until: Option<Until>) {
+        self.stopped = false;
+        self.until = until;
+        self.resumed_at = Some(emu.clock.next_instruction);
+    }
+
+    /// Executes `count` instructions, ignoring breakpoints, and stays stopped
+    fn step(&mut self, emu: &mut Emulator, count: u16) -> Result<String, String> {
+        self.stop();
+
+        let mut executed = 0;
+
+        // Waiting for vertical blank only takes until the next timer update
+        while executed < count {
+            match emu.run_instruction() {
+                Ok(StepOutcome::Exited) => return Ok(String::from("program exited")),
+                Ok(StepOutcome::WaitingForKey) => {
+                    return Ok(String::from("waiting for a key press"))
+                }
+                Ok(StepOutcome::WaitingForVblank) => {}
+                Ok(StepOutcome::Watchpoint) => break,
+                Ok(_) => executed += 1,
+                Err(err) => return Err(err.to_string()),
+            }
+        }
+
+        Ok(self.status(emu))
+    }
+
+    /// Lists `count` instructions from `addr`, marking `pc` and breakpoints
+    fn disassemble(&self, emu: &Emulator, mut addr: u16, count: u16) -> String {
+        let mut out = String::new();
+
+        for _ in 0..count {
+            let (hi, lo) = match (
+                emu.memory.get(addr as usize),
+                emu.memory.get(addr as usize + 1),
+            ) {
+                (Some(&hi), Some(&lo)) => (hi, lo),
+                _ => break,
+            };
+            let inst = Instruction::decode((hi as u16) << 8 | lo as u16);
+            let pc = if addr == emu.pc { '>' } else { ' ' };
+            let bp = if self.breakpoints.iter().any(|bp| bp.addr == Some(addr)) {
+                '*'
+            } else {
+                ' '
+            };
+
+            writeln!(
+                out,
+                "{}{} {:#06x}: {:02x}{:02x}  {}",
+                pc, bp, addr, hi, lo, inst
+            )
+            .unwrap();
+            addr = addr.wrapping_add(inst.size());
+        }
+
+        out
+    }
+}
+
+/// Hex dump of `len` bytes from `addr`, 16 per line
+fn memory(emu: &Emulator, addr: u16, len: u16) -> String {
+    let start = (addr as usize).min(emu.memory.len());
+    let end = (start + len as usize).min(emu.memory.len());
+    let mut out = String::new();
+
+    for (n, row) in emu.memory[start..end].chunks(16).enumerate() {
+        write!(out, "{:#06x}:", start + n * 16).unwrap();
+        for byte in row {
+            write!(out, " {:02x}", byte).unwrap();
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Parses `ADDR`, `ADDR if COND` or `if COND`
+fn parse_breakpoint(emu: &Emulator, text: &str) -> Result<Breakpoint, String> {
+    let (addr, cond) = if let Some(cond) = text.strip_prefix("if ") {
+        ("", Some(cond))
+    } else if let Some((addr, cond)) = text.split_once(" if ") {
+        (addr.trim(), Some(cond))
+    } else {
+        (text, None)
+    };
+
+    let breakpoint = Breakpoint {
+        addr: match addr {
+            "" => None,
+            addr => Some(parse_addr(emu, addr)?),
+        },
+        condition: cond.map(Condition::parse).transpose()?,
+    };
+
+    if breakpoint.addr.is_none() && breakpoint.condition.is_none() {
+        return Err(String::from("usage: break ADDR [if COND] or break if COND"));
+    }
+
+    Ok(breakpoint)
+}
+
+/// Parses an address, either a number or the current value of `pc` or `i`
+fn parse_addr(emu: &Emulator, text: &str) -> Result<u16, String> {
+    match text.to_ascii_lowercase().as_str() {
+        "pc" => Ok(emu.pc),
+        "i" => Ok(emu.i),
+        _ => parse_number(text),
+    }
+}
+
+/// Parses a decimal, `0x` hex or `0b` binary number
+fn parse_number(text: &str) -> Result<u16, String> {
+    let lower = text.to_ascii_lowercase();
+    let parsed = if let Some(hex) = lower.strip_prefix("0x") {
+        u16::from_str_radix(hex, 16)
+    } else if let Some(bin) = lower.strip_prefix("0b") {
+        u16::from_str_radix(bin, 2)
+    } else {
+        lower.parse()
+    };
+
+    parsed.map_err(|_| format!("invalid number '{}'", text))
+}
@@ -0,0 +1,158 @@
+use std::fmt::Write;
+
+use crate::{emu::ROM_OFFSET, instruction::Instruction};
+
+/// What a word of the ROM turned out to be while tracing the program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Word {
+    /// Not reached from the entry point
+    Data,
+    /// Start of an instruction
+    Code,
+    /// Address following `LoadLongI`
+    Operand,
+}
+
+/// Disassembles ROM into a listing like `compiler/roms/pong.s`.
+///
+/// Code is found by following every path from the start of the ROM, anything never
+/// reached is listed as `dat` words. Targets of `call` and `jmp` get `fn_XXXX` and
+/// `label_XXXX` labels, named by their offset into the ROM. Every line is annotated
+/// with its opcode and address.
+pub fn disassemble(rom: &[u8]) -> String {
+    let words = trace(rom);
+    let mut calls = vec![false; words.len()];
+    let mut jumps = vec![false; words.len()];
+
+    for (idx, word) in words.iter().enumerate() {
+        if *word != Word::Code {
+            continue;
+        }
+
+        match Instruction::decode(opcode(rom, idx * 2)) {
+            Instruction::Call(addr) => mark(&mut calls, &words, addr),
+            Instruction::Jump(addr) => mark(&mut jumps, &words, addr),
+            _ => {}
+        }
+    }
+
+    let label = |addr: u16| -> Option<String> {
+        let idx = (addr as usize).checked_sub(ROM_OFFSET)? / 2;
+        if !addr.is_multiple_of(2) || idx >= words.len() {
+            None
+        } else if idx == 0 {
+            Some(String::from("main"))
+        } else if calls[idx] {
+            Some(format!("fn_{:04x}", idx * 2))
+        } else if jumps[idx] {
+            Some(format!("label_{:04x}", idx * 2))
+        } else {
+            None
+        }
+    };
+
+    let mut out = String::new();
+
+    for (idx, word) in words.iter().enumerate() {
+        let offset = idx * 2;
+        let addr = (ROM_OFFSET + offset) as u16;
+        let op = opcode(rom, offset);
+
+        if let Some(name) = label(addr) {
+            writeln!(out, "{}:", name).unwrap();
+        } else if *word == Word::Data && (idx == 0 || words[idx - 1] != Word::Data) {
+            writeln!(out, "data_{:04x}:", offset).unwrap();
+        }
+
+        // Odd sized ROMs end with a lone byte
+        if offset + 1 == rom.len() {
+            let text = format!("db 0x{:02x}", rom[offset]);
+            writeln!(
+                out,
+                "  {:<28}; 0x{:02x} @ 0x{:04x}",
+                text, rom[offset], addr
+            )
+            .unwrap();
+            break;
+        }
+
+        let text = match *word {
+            Word::Data | Word::Operand => format!("dat 0x{:04x}", op),
+            Word::Code => match Instruction::decode(op) {
+                Instruction::Call(target) => match label(target) {
+                    Some(name) => format!("call {}", name),
+                    None => Instruction::Call(target).to_string(),
+                },
+                Instruction::Jump(target) => match label(target) {
+                    Some(name) => format!("jmp {}", name),
+                    None => Instruction::Jump(target).to_string(),
+                },
+                inst => inst.to_string(),
+            },
+        };
+
+        writeln!(out, "  {:<28}; 0x{:04x} @ 0x{:04x}", text, op, addr).unwrap();
+    }
+
+    out
+}
+
+/// Opcode at given offset into the ROM, a missing low byte reads as 0
+fn opcode(rom: &[u8], offset: usize) -> u16 {
+    (rom[offset] as u16) << 8 | *rom.get(offset + 1).unwrap_or(&0) as u16
+}
+
+/// Marks address as a label target, if it is the start of an instruction
+fn mark(targets: &mut [bool], words: &[Word], addr: u16) {
+    if let Some(offset) = (addr as usize).checked_sub(ROM_OFFSET) {
+        if offset.is_multiple_of(2) && words.get(offset / 2) == Some(&Word::Code) {
+            targets[offset / 2] = true;
+        }
+    }
+}
+
+/// Finds code by following all jumps, calls and skips from the start of the ROM.
+///
+/// Returns what every word of the ROM is. Code reached only through `jmp #addr+V0`
+/// or at odd addresses is not found.
+fn trace(rom: &[u8]) -> Vec<Word> {
+    let mut words = vec![Word::Data; rom.len().div_ceil(2)];
+    let mut pending = vec![ROM_OFFSET as u16];
+
+    while let Some(addr) = pending.pop() {
+        let offset = match (addr as usize).checked_sub(ROM_OFFSET) {
+            Some(offset) if offset.is_multiple_of(2) && offset + 1 < rom.len() => offset,
+            _ => continue,
+        };
+        if words[offset / 2] != Word::Data {
+            continue;
+        }
+        words[offset / 2] = Word::Code;
+
+        let inst = Instruction::decode(opcode(rom, offset));
+        let next = addr.wrapping_add(inst.size());
+
+        match inst {
+            Instruction::Ret | Instruction::Exit | Instruction::JumpV0(_) => {}
+            Instruction::Unknown(_) => {}
+            Instruction::Jump(target) => pending.push(target),
+            Instruction::Call(target) => pending.extend_from_slice(&[target, next]),
+            Instruction::LoadLongI => {
+                if let Some(word) = words.get_mut(offset / 2 + 1) {
+                    *word = Word::Operand;
+                }
+                pending.push(next);
+            }
+            _ if inst.is_skip() => {
+                let skipped = match rom.get(offset + 2..offset + 4) {
+                    Some([0xF0, 0x00]) => 4,
+                    _ => 2,
+                };
+                pending.extend_from_slice(&[next, next.wrapping_add(skipped)]);
+            }
+            _ => pending.push(next),
+        }
+    }
+
+    words
+}
@@ -0,0 +1,1254 @@
+use std::time::Duration;
+
+use crate::{
+    clock::{Clock, DEFAULT_IPS},
+    error::EmulatorError,
+    instruction::Instruction,
+    movie::Movie,
+    quirks::Quirks,
+    rng::Rng,
+    state::rom_hash,
+    trace::{TraceEntry, Tracer},
+    watch::{WatchHit, Watchpoint},
+};
+
+/// Offset in CHIP8 RAM where actual ROM (program) starts
+pub const ROM_OFFSET: usize = 0x200;
+/// Size of the RAM in bytes, XO-CHIP extends it from 4kb to 64kb
+pub const MEMORY_SIZE: usize = 0x10000;
+/// Largest ROM that fits in RAM after `ROM_OFFSET`
+pub const MAX_ROM_SIZE: usize = MEMORY_SIZE - ROM_OFFSET;
+/// Number of pixels horizontally
+pub const COLS: usize = 64;
+/// Number of pixels vertically
+pub const ROWS: usize = 32;
+/// Total number of pixels
+pub const PIXELS: usize = ROWS * COLS;
+/// Number of pixels horizontally in SUPER-CHIP high resolution mode
+pub const HIRES_COLS: usize = 128;
+/// Number of pixels vertically in SUPER-CHIP high resolution mode
+pub const HIRES_ROWS: usize = 64;
+/// Total number of pixels in SUPER-CHIP high resolution mode
+pub const HIRES_PIXELS: usize = HIRES_ROWS * HIRES_COLS;
+/// Offset in CHIP8 RAM where the SUPER-CHIP big (8x10) font starts, right after the small one
+pub const BIG_FONT_OFFSET: usize = 0x50;
+/// Number of XO-CHIP bitplanes, each pixel in `display` is a bitmask of planes it is set in
+pub const PLANES: usize = 2;
+/// Default XO-CHIP audio pitch, which plays the pattern buffer at 4000Hz
+pub const DEFAULT_PITCH: u8 = 64;
+
+/// Result of successfully running the Emulator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// Instruction(s) were executed
+    Executed,
+    /// Execution is halted until a key is pressed (`Fx0A`)
+    WaitingForKey,
+    /// Execution is halted until the next timer update, see `Quirks::display_wait`
+    WaitingForVblank,
+    /// Program exited through SUPER-CHIP `00FD`
+    Exited,
+    /// Last instruction accessed memory under a watchpoint, see `Emulator::watch_hit`
+    Watchpoint,
+    /// Stopped before the instruction at `pc`, as asked by the caller of `run_for_until`
+    Breakpoint,
+}
+
+impl StepOutcome {
+    /// Whether running stopped before the requested time was up
+    pub fn is_stop(self) -> bool {
+        matches!(
+            self,
+            StepOutcome::Exited | StepOutcome::Breakpoint | StepOutcome::Watchpoint
+        )
+    }
+}
+
+/// CHIP-8 Emulator based on http://devernay.free.fr/hacks/chip8/C8TECH10.HTM
+#[derive(Clone)]
+pub struct Emulator {
+    /// The RAM of Emulator (64kb)
+    pub memory: Vec<u8>,
+    /// 16 16-bit registers
+    pub v: [u8; 0x10],
+    /// Represents stack. Actually upto `sp`, rest are 0s
+    pub stack: [u16; 0x10],
+    /// Represents current top-most stack item index/pointer
+    pub sp: u8,
+    /// Display memory, bitmask of XO-CHIP planes set for each pixel.
+    /// Only plane 1 is used by CHIP-8 and SUPER-CHIP, i.e. just 0s and 1s. Holds `width() * height()` pixels
+    pub display: Vec<u8>,
+    /// Bitmask of XO-CHIP planes affected by drawing, clearing and scrolling
+    pub planes: u8,
+    /// XO-CHIP 1-bit audio pattern (128 samples), played while sound timer is active
+    pub audio_pattern: [u8; 0x10],
+    /// XO-CHIP audio pitch, sets the pattern playback rate to `4000 * 2^((pitch - 64) / 48)` Hz
+    pub pitch: u8,
+    /// Whether SUPER-CHIP high resolution (128x64) mode is enabled
+    pub hires: bool,
+    /// SUPER-CHIP RPL user flags, saved and restored by `Fx75`/`Fx85`
+    pub rpl: [u8; 0x10],
+    /// Whether program has exited through `00FD`
+    pub exited: bool,
+    /// Keys that are currently pressed
+    pub keyboard: [bool; 0x10],
+    /// Whether emulator is paused or not
+    pub paused: bool,
+    /// Emulator speed (instructions executed per second)
+    pub ips: u32,
+    /// Emulated time, schedules instructions and timer updates
+    pub clock: Clock,
+    /// Whether emulator will be unpaused on next key_down,
+    /// if it will, then `pc` will also be set to given value
+    pub unpause_next: Option<u8>,
+    /// Represents program counter, position to current instruction/opcode in memory.
+    pub pc: u16,
+    /// Just another register.
+    pub i: u16,
+    /// Delay timer
+    pub dt: u8,
+    /// Sound timer
+    pub st: u8,
+    /// Behaviour of ambiguous instructions
+    pub quirks: Quirks,
+    /// Whether a sprite was drawn since the last timer update, see `Quirks::display_wait`
+    pub vblank_wait: bool,
+    /// Hash of the loaded ROM, see `state::rom_hash`
+    pub rom_hash: u64,
+    /// Seed the random number generator was created with
+    pub seed: u64,
+    /// Random number generator used by `Cxkk`
+    pub rng: Rng,
+    /// Memory ranges that stop execution when accessed by instructions
+    pub watchpoints: Vec<Watchpoint>,
+    /// First watched access made by the last instruction executed
+    pub watch_hit: Option<WatchHit>,
+    /// Instruction being executed as fetched, for watchpoint hits in case it overwrites itself
+    executing: Instruction,
+    /// Records every instruction executed, if set
+    pub tracer: Option<Tracer>,
+    /// Records every key press and release, if set
+    pub movie: Option<Movie>,
+    /// Instruction starting at every address of RAM, empty unless enabled with `set_decode_cache`
+    decode_cache: Vec<Instruction>,
+}
+
+impl Emulator {
+    /// Create new Emulator instance, with a random seed
+    pub fn new() -> Self {
+        Self::with_seed(rand::random())
+    }
+
+    /// Create new Emulator instance, whose `Cxkk` yields the same numbers for the same seed
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            memory: vec![0; MEMORY_SIZE],
+            v: [0; 0x10],
+            // Initialize with all pixels 0, that is black screen
+            display: vec![0; PIXELS],
+            hires: false,
+            planes: 1,
+            audio_pattern: [0; 0x10],
+            pitch: DEFAULT_PITCH,
+            rpl: [0; 0x10],
+            exited: false,
+            stack: [0; 0x10],
+            sp: 0,
+            // Initialize with all keys up
+            keyboard: [false; 0x10],
+            unpause_next: None,
+            // Execution starts where ROM is loaded
+            pc: ROM_OFFSET as u16,
+            i: 0,
+            paused: false,
+            ips: DEFAULT_IPS,
+            clock: Clock::default(),
+            dt: 0,
+            st: 0,
+            quirks: Quirks::default(),
+            vblank_wait: false,
+            rom_hash: rom_hash(&[]),
+            seed,
+            rng: Rng::new(seed),
+            watchpoints: Vec::new(),
+            watch_hit: None,
+            executing: Instruction::decode(0),
+            tracer: None,
+            movie: None,
+            decode_cache: Vec::new(),
+        }
+    }
+
+    /// Load sprites/fontset into RAM
+    pub fn load_sprites(&mut self) {
+        let sprites: [u8; 0x50] = [
+            0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+            0x20, 0x60, 0x20, 0x20, 0x70, // 1
+            0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+            0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+            0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+            0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+            0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+            0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+            0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+            0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+            0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+            0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+            0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+            0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+            0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+            0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+        ];
+
+        self.memory[..sprites.len()].copy_from_slice(&sprites);
+
+        // SUPER-CHIP big hexadecimal font, used by `Fx30`
+        let big_sprites: [u8; 0xA0] = [
+            0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+            0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+            0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+            0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+            0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+            0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+            0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+            0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+            0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+            0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+            0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+            0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+            0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+            0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+            0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+            0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+        ];
+
+        self.memory[BIG_FONT_OFFSET..BIG_FONT_OFFSET + big_sprites.len()]
+            .copy_from_slice(&big_sprites);
+
+        self.invalidate_decode_cache();
+    }
+
+    /// Load ROM into RAM
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), EmulatorError> {
+        if rom.len() > MAX_ROM_SIZE {
+            return Err(EmulatorError::RomTooLarge {
+                size: rom.len(),
+                max: MAX_ROM_SIZE,
+            });
+        }
+
+        self.memory[ROM_OFFSET..ROM_OFFSET + rom.len()].copy_from_slice(rom);
+        self.rom_hash = rom_hash(rom);
+        self.invalidate_decode_cache();
+        Ok(())
+    }
+
+    /// Enables or disables the decode cache.
+    ///
+    /// When enabled, all of RAM is decoded up front and kept up to date as the program writes
+    /// to memory, so instructions are not decoded again every time they run. This speeds up
+    /// long runs, at the cost of decoding everything again whenever a whole new RAM is loaded.
+    pub fn set_decode_cache(&mut self, enabled: bool) {
+        if enabled {
+            self.decode_cache = vec![Instruction::Cls; MEMORY_SIZE];
+            self.invalidate_decode_cache();
+        } else {
+            self.decode_cache = Vec::new();
+        }
+    }
+
+    /// Whether the decode cache is enabled
+    pub fn decode_cache_enabled(&self) -> bool {
+        !self.decode_cache.is_empty()
+    }
+
+    /// Decodes all of RAM into the decode cache again, if it is enabled.
+    ///
+    /// Needed after changing `memory` directly, writes made by instructions update it already.
+    pub fn invalidate_decode_cache(&mut self) {
+        for addr in 0..self.decode_cache.len() {
+            self.decode_cache[addr] = self.decode_at(addr);
+        }
+    }
+
+    /// Records the first access of the current instruction that hits a watchpoint.
+    ///
+    /// Every instruction accessing memory has already moved `pc` past itself by 2.
+    fn watch(&mut self, addr: usize, old: u8, new: Option<u8>) {
+        if self.watch_hit.is_some() {
+            return;
+        }
+
+        if let Some(&watchpoint) = self.watchpoints.iter().find(|w| w.matches(addr, old, new)) {
+            let pc = self.pc.wrapping_sub(2);
+
+            self.watch_hit = Some(WatchHit {
+                watchpoint,
+                addr: addr as u16,
+                old,
+                new,
+                pc,
+                instruction: self.executing,
+            });
+        }
+    }
+
+    /// Decodes instruction starting at given address, without checking it fits in RAM
+    fn decode_at(&self, addr: usize) -> Instruction {
+        let low = self.memory.get(addr + 1).copied().unwrap_or(0);
+        Instruction::decode((self.memory[addr] as u16) << 8 | low as u16)
+    }
+
+    /// Reads byte from RAM for an instruction, checking read watchpoints
+    fn read_memory(&mut self, addr: usize) -> u8 {
+        let value = self.memory[addr];
+        self.watch(addr, value, None);
+        value
+    }
+
+    /// Writes byte to RAM for an instruction, checking write watchpoints and updating the
+    /// decode cache for both instructions it is part of
+    fn write_memory(&mut self, addr: usize, value: u8) {
+        self.watch(addr, self.memory[addr], Some(value));
+        self.memory[addr] = value;
+
+        if self.decode_cache_enabled() {
+            for start in addr.saturating_sub(1)..=addr {
+                self.decode_cache[start] = self.decode_at(start);
+            }
+        }
+    }
+
+    /// Number of pixels horizontally in current resolution
+    pub fn width(&self) -> usize {
+        if self.hires {
+            HIRES_COLS
+        } else {
+            COLS
+        }
+    }
+
+    /// Number of pixels vertically in current resolution
+    pub fn height(&self) -> usize {
+        if self.hires {
+            HIRES_ROWS
+        } else {
+            ROWS
+        }
+    }
+
+    /// Switches between low (64x32) and high (128x64) resolution, clearing the display
+    pub fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        self.display = vec![0; self.width() * self.height()];
+    }
+
+    /// Toggle (XOR) pixel in given plane at given location. Given location may be changed to keep it in bounds.
+    ///
+    /// Returns whether the pixel was erased, i.e. a collision.
+    fn set_pixel(&mut self, x: isize, y: isize, plane: u8) -> bool {
+        let cols = self.width() as isize;
+        let rows = self.height() as isize;
+        let x = x.rem_euclid(cols);
+        let y = y.rem_euclid(rows);
+
+        let idx = ((y * cols) + x) as usize;
+        let was_set = self.display[idx] & plane != 0;
+        self.display[idx] ^= plane;
+
+        was_set
+    }
+
+    /// Clears display memory of selected planes, i.e. fill it with 0s.
+    /// Results in all black* display.
+    pub fn clear_display(&mut self) {
+        let planes = self.planes;
+        self.display.iter_mut().for_each(|px| *px &= !planes);
+    }
+
+    /// Scrolls selected planes by given number of pixels (positive is right/down),
+    /// filling the uncovered area with 0s
+    pub fn scroll(&mut self, dx: isize, dy: isize) {
+        let cols = self.width() as isize;
+        let rows = self.height() as isize;
+        let planes = self.planes;
+        let old = self.display.clone();
+
+        for y in 0..rows {
+            for x in 0..cols {
+                let (sx, sy) = (x - dx, y - dy);
+                let src = if sx >= 0 && sx < cols && sy >= 0 && sy < rows {
+                    old[(sy * cols + sx) as usize]
+                } else {
+                    0
+                };
+
+                let px = &mut self.display[(y * cols + x) as usize];
+                *px = (*px & !planes) | (src & planes);
+            }
+        }
+    }
+
+    /// Skips next instruction, which is 4 bytes long in case of XO-CHIP `F000 nnnn`
+    fn skip_next(&mut self) {
+        let next = self.pc as usize;
+        let long =
+            next + 1 < MEMORY_SIZE && self.memory[next] == 0xF0 && self.memory[next + 1] == 0x00;
+
+        self.pc = self.pc.wrapping_add(if long { 4 } else { 2 });
+    }
+
+    /// Moves `I` past the `count` registers just stored or loaded, as far as quirks ask for
+    fn increment_i_after_load_store(&mut self, count: usize) {
+        if !self.quirks.load_store_increments_i {
+            return;
+        }
+
+        // Wraps around like `Fx1E` once the registers reached the end of memory
+        let count = count as u16;
+        if self.quirks.load_store_increments_i_by_x {
+            self.i = self.i.wrapping_add(count - 1);
+        } else {
+            self.i = self.i.wrapping_add(count);
+        }
+    }
+
+    /// Checks whether a key is pressed or not
+    pub fn is_key_pressed(&self, code: u8) -> bool {
+        self.keyboard[code as usize]
+    }
+
+    /// Holds down the key if its not pressed, otherwise noop
+    pub fn key_down(&mut self, code: u8) {
+        if !self.is_key_pressed(code) {
+            self.keyboard[code as usize] = true;
+            if let Some(movie) = &mut self.movie {
+                movie.record(&self.clock, code, true);
+            }
+
+            if self.unpause_next.is_some() {
+                self.paused = false;
+                self.v[self.unpause_next.unwrap() as usize] = code;
+                self.unpause_next = None;
+            }
+        }
+    }
+
+    /// Releases the key if it is pressed, otherwise noop
+    pub fn key_up(&mut self, code: u8) {
+        if self.is_key_pressed(code) {
+            self.keyboard[code as usize] = false;
+            if let Some(movie) = &mut self.movie {
+                movie.record(&self.clock, code, false);
+            }
+        }
+    }
+
+    /// Gets current opcode/instruction. Basically `fetch` part of cycle.
+    pub fn opcode(&self) -> Result<u16, EmulatorError> {
+        let pc = check_addr(self.pc as usize + 1)? - 1;
+        Ok(((self.memory[pc] as u16) << 8) | self.memory[pc + 1] as u16)
+    }
+
+    /// Gets current instruction, from the decode cache if enabled
+    pub fn fetch(&self) -> Result<Instruction, EmulatorError> {
+        if self.decode_cache_enabled() {
+            let pc = check_addr(self.pc as usize + 1)? - 1;
+            Ok(self.decode_cache[pc])
+        } else {
+            Ok(Instruction::decode(self.opcode()?))
+        }
+    }
+
+    /// Runs a CPU cycle (frame), i.e. all instructions due before the next timer update, followed by it.
+    pub fn cycle(&mut self) -> Result<StepOutcome, EmulatorError> {
+        self.cycle_until(&mut |_| false)
+    }
+
+    /// Runs a CPU cycle, stopping early at instructions `break_at` returns true for
+    fn cycle_until(
+        &mut self,
+        break_at: &mut dyn FnMut(&Emulator) -> bool,
+    ) -> Result<StepOutcome, EmulatorError> {
+        let outcome = self.run_until(self.clock.next_timer(), break_at)?;
+
+        if !outcome.is_stop() {
+            self.update_timers();
+            self.clock.advance_timer();
+        }
+
+        Ok(outcome)
+    }
+
+    /// Runs the Emulator for given amount of emulated time.
+    ///
+    /// Instructions run at `ips` and timers are updated at exactly 60Hz, independent of how
+    /// often this is called. After a stall, all due instructions and timer updates are caught
+    /// up in order, so callers wanting to skip time instead should clamp `duration`.
+    ///
+    /// Stops early with `StepOutcome::Watchpoint` right after an instruction hits one of
+    /// `watchpoints`.
+    pub fn run_for(&mut self, duration: Duration) -> Result<StepOutcome, EmulatorError> {
+        self.run_for_until(duration, |_| false)
+    }
+
+    /// Like `run_for`, but checks `break_at` before every instruction and returns
+    /// `StepOutcome::Breakpoint` without executing it once that returns true.
+    ///
+    /// The check is skipped while waiting for a key press or vertical blank. The rest of
+    /// `duration` is dropped on a break or watchpoint, so the clock stays where it stopped.
+    pub fn run_for_until<F>(
+        &mut self,
+        duration: Duration,
+        mut break_at: F,
+    ) -> Result<StepOutcome, EmulatorError>
+    where
+        F: FnMut(&Emulator) -> bool,
+    {
+        let end = self.clock.now + duration.as_nanos() as u64;
+        let mut outcome = StepOutcome::Executed;
+
+        while self.clock.next_timer() <= end {
+            outcome = self.cycle_until(&mut break_at)?;
+
+            if outcome.is_stop() {
+                return Ok(outcome);
+            }
+        }
+
+        if self.clock.next_instruction < end {
+            outcome = self.run_until(end, &mut break_at)?;
+
+            if outcome == StepOutcome::Breakpoint || outcome == StepOutcome::Watchpoint {
+                return Ok(outcome);
+            }
+        }
+
+        self.clock.now = end;
+        Ok(outcome)
+    }
+
+    /// Runs exactly the next instruction slot, along with any timer updates due before it.
+    ///
+    /// Nothing is executed while waiting for a key press or vertical blank, but time still
+    /// moves on by one instruction.
+    pub fn run_instruction(&mut self) -> Result<StepOutcome, EmulatorError> {
+        while self.clock.next_timer() <= self.clock.next_instruction {
+            self.update_timers();
+            self.clock.advance_timer();
+        }
+
+        self.clock.now = self.clock.next_instruction;
+        let outcome = self.step()?;
+        self.clock.advance_instruction(self.ips);
+
+        Ok(outcome)
+    }
+
+    /// Executes all instructions due before given time, without updating timers
+    fn run_until(
+        &mut self,
+        end: u64,
+        break_at: &mut dyn FnMut(&Emulator) -> bool,
+    ) -> Result<StepOutcome, EmulatorError> {
+        let mut outcome = StepOutcome::Executed;
+
+        while self.clock.next_instruction < end {
+            let waiting = self.exited || self.paused || self.vblank_wait;
+            if !waiting && break_at(self) {
+                return Ok(StepOutcome::Breakpoint);
+            }
+
+            self.clock.now = self.clock.next_instruction;
+            outcome = self.step()?;
+            self.clock.advance_instruction(self.ips);
+
+            if outcome == StepOutcome::Exited || outcome == StepOutcome::Watchpoint {
+                break;
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    /// Executes a single instruction, unless waiting for a key press or vertical blank.
+    ///
+    /// On error, `pc` is left pointing at the offending instruction.
+    pub fn step(&mut self) -> Result<StepOutcome, EmulatorError> {
+        if self.exited {
+            return Ok(StepOutcome::Exited);
+        }
+
+        if self.paused {
+            return Ok(StepOutcome::WaitingForKey);
+        }
+
+        if self.vblank_wait {
+            return Ok(StepOutcome::WaitingForVblank);
+        }
+
+        let pc = self.pc;
+        self.watch_hit = None;
+
+        // Taken before executing, in case the instruction overwrites itself
+        let traced = match self.tracer {
+            Some(_) => self.opcode().ok().map(|opcode| (opcode, self.v)),
+            None => None,
+        };
+
+        if let Err(err) = self.execute_instruction() {
+            self.pc = pc;
+            return Err(err);
+        }
+
+        if let Some((opcode, before)) = traced {
+            self.trace(pc, opcode, before);
+        }
+
+        if self.exited {
+            Ok(StepOutcome::Exited)
+        } else if self.watch_hit.is_some() {
+            Ok(StepOutcome::Watchpoint)
+        } else {
+            Ok(StepOutcome::Executed)
+        }
+    }
+
+    /// Records instruction just executed at `pc` with the tracer, if its address is traced
+    fn trace(&mut self, pc: u16, opcode: u16, before: [u8; 0x10]) {
+        let (v, i, sp) = (self.v, self.i, self.sp);
+
+        if let Some(tracer) = &mut self.tracer {
+            if tracer.traces(pc) {
+                tracer.record(TraceEntry {
+                    cycle: tracer.cycles,
+                    pc,
+                    opcode,
+                    before,
+                    v,
+                    i,
+                    sp,
+                });
+            }
+            tracer.cycles += 1;
+        }
+    }
+
+    /// Updates delay and sound timers
+    fn update_timers(&mut self) {
+        self.vblank_wait = false;
+
+        if self.dt > 0 {
+            self.dt -= 1;
+        }
+
+        if self.st > 0 {
+            self.st -= 1;
+        }
+    }
+
+    /// Executes current instruction
+    fn execute_instruction(&mut self) -> Result<(), EmulatorError> {
+        let inst = self.fetch()?;
+        self.executing = inst;
+        // Like on XO-CHIP, the program counter wraps around the end of memory
+        self.pc = self.pc.wrapping_add(2);
+
+        match inst {
+            // 00E0 - CLS
+            // Clear the display.
+            Instruction::Cls => {
+                self.clear_display();
+            }
+
+            // 00EE - RET
+            // Return from a subroutine.
+            //
+            // The interpreter sets the program counter to the address at the top of the stack, then subtracts 1 from the stack pointer.
+            Instruction::Ret => {
+                if self.sp == 0 {
+                    return Err(EmulatorError::StackUnderflow {
+                        pc: self.pc.wrapping_sub(2),
+                    });
+                }
+
+                // NOTE: Why did I have to subtract 1 before setting pc?
+                self.sp -= 1;
+                self.pc = self.stack[self.sp as usize];
+                self.stack[self.sp as usize] = 0;
+            }
+
+            // 00Cn - SCD nibble (SUPER-CHIP)
+            // Scroll display down by n rows.
+            Instruction::ScrollDown(n) => {
+                self.scroll(0, n as isize);
+            }
+
+            // 00Dn - SCU nibble (XO-CHIP)
+            // Scroll display up by n rows.
+            Instruction::ScrollUp(n) => {
+                self.scroll(0, -(n as isize));
+            }
+
+            // 00FB - SCR (SUPER-CHIP)
+            // Scroll display right by 4 pixels.
+            Instruction::ScrollRight => {
+                self.scroll(4, 0);
+            }
+
+            // 00FC - SCL (SUPER-CHIP)
+            // Scroll display left by 4 pixels.
+            Instruction::ScrollLeft => {
+                self.scroll(-4, 0);
+            }
+
+            // 00FD - EXIT (SUPER-CHIP)
+            // Exit the interpreter.
+            Instruction::Exit => {
+                self.exited = true;
+            }
+
+            // 00FE - LOW (SUPER-CHIP)
+            // Disable high resolution mode.
+            Instruction::Low => {
+                self.set_hires(false);
+            }
+
+            // 00FF - HIGH (SUPER-CHIP)
+            // Enable high resolution mode.
+            Instruction::High => {
+                self.set_hires(true);
+            }
+
+            // 0nnn - SYS addr
+            // Jump to a machine code routine at nnn.
+            //
+            // This instruction is only used on the old computers on which Chip-8 was originally implemented. It is ignored by modern interpreters.
+            Instruction::Sys(_) => {}
+
+            // 1nnn - JP addr
+            // Jump to location nnn.
+            //
+            // The interpreter sets the program counter to nnn.
+            Instruction::Jump(addr) => {
+                self.pc = addr;
+            }
+
+            // 2nnn - CALL addr
+            // Call subroutine at nnn.
+            //
+            // The interpreter increments the stack pointer, then puts the current PC on the top of the stack. The PC is then set to nnn.
+            Instruction::Call(addr) => {
+                if self.sp as usize >= self.stack.len() {
+                    return Err(EmulatorError::StackOverflow {
+                        pc: self.pc.wrapping_sub(2),
+                    });
+                }
+
+                self.stack[self.sp as usize] = self.pc;
+                // NOTE: Why did I have to increment it after setting pc?
+                self.sp += 1;
+                self.pc = addr;
+            }
+
+            // 3xkk - SE Vx, byte
+            // Skip next instruction if Vx = kk.
+            //
+            // The interpreter compares register Vx to kk, and if they are equal, increments the program counter by 2.
+            Instruction::SkipEqByte(x, kk) => {
+                let vx = self.v[x as usize];
+                if vx == kk {
+                    self.skip_next();
+                }
+            }
+
+            // 4xkk - SNE Vx, byte
+            // Skip next instruction if Vx != kk.
+            //
+            // The interpreter compares register Vx to kk, and if they are not equal, increments the program counter by 2.
+            Instruction::SkipNeByte(x, kk) => {
+                let vx = self.v[x as usize];
+                if vx != kk {
+                    self.skip_next();
+                }
+            }
+
+            // 5xy0 - SE Vx, Vy
+            // Skip next instruction if Vx = Vy.
+            //
+            // The interpreter compares register Vx to register Vy, and if they are equal, increments the program counter by 2.
+            Instruction::SkipEq(x, y) => {
+                let vx = self.v[x as usize];
+                let vy = self.v[y as usize];
+
+                if vx == vy {
+                    self.skip_next();
+                }
+            }
+
+            // 5xy2 - LD [I], Vx-Vy (XO-CHIP)
+            // Store Vx through Vy in memory starting at location I, in reverse order if x > y. I is not changed.
+            Instruction::StoreRange(x, y) => {
+                let regs = register_range(x, y);
+                check_addr(self.i as usize + regs.len() - 1)?;
+
+                for (offset, reg) in regs.into_iter().enumerate() {
+                    self.write_memory(self.i as usize + offset, self.v[reg]);
+                }
+            }
+
+            // 5xy3 - LD Vx-Vy, [I] (XO-CHIP)
+            // Read Vx through Vy from memory starting at location I, in reverse order if x > y. I is not changed.
+            Instruction::LoadRange(x, y) => {
+                let regs = register_range(x, y);
+                check_addr(self.i as usize + regs.len() - 1)?;
+
+                for (offset, reg) in regs.into_iter().enumerate() {
+                    self.v[reg] = self.read_memory(self.i as usize + offset);
+                }
+            }
+
+            // 6xkk - LD Vx, byte
+            // Set Vx = kk.
+            //
+            // The interpreter puts the value kk into register Vx.
+            Instruction::LoadByte(x, kk) => {
+                self.v[x as usize] = kk;
+            }
+
+            // 7xkk - ADD Vx, byte
+            // Set Vx = Vx + kk.
+            //
+            // Adds the value kk to the value of register Vx, then stores the result in Vx.
+            Instruction::AddByte(x, kk) => {
+                let mut vx = self.v[x as usize] as u16;
+                vx += kk as u16;
+                self.v[x as usize] = (vx & 0xFF) as u8; // NOTE: I had to & 0xff to prevent overflow, but spec does not say so
+            }
+
+            // 8xy0 - LD Vx, Vy
+            // Set Vx = Vy.
+            //
+            // Stores the value of register Vy in register Vx.
+            Instruction::Load(x, y) => {
+                self.v[x as usize] = self.v[y as usize];
+            }
+
+            // 8xy1 - OR Vx, Vy
+            // Set Vx = Vx OR Vy.
+            //
+            // Performs a bitwise OR on the values of Vx and Vy, then stores the result in Vx. A bitwise OR compares the corrseponding bits from two values, and if either bit is 1, then the same bit in the result is also 1. Otherwise, it is 0.
+            Instruction::Or(x, y) => {
+                self.v[x as usize] |= self.v[y as usize];
+
+                if self.quirks.logic_resets_vf {
+                    self.v[0xF] = 0;
+                }
+            }
+
+            // 8xy2 - AND Vx, Vy
+            // Set Vx = Vx AND Vy.
+            //
+            // Performs a bitwise AND on the values of Vx and Vy, then stores the result in Vx. A bitwise AND compares the corrseponding bits from two values, and if both bits are 1, then the same bit in the result is also 1. Otherwise, it is 0.
+            Instruction::And(x, y) => {
+                self.v[x as usize] &= self.v[y as usize];
+
+                if self.quirks.logic_resets_vf {
+                    self.v[0xF] = 0;
+                }
+            }
+
+            // 8xy3 - XOR Vx, Vy
+            // Set Vx = Vx XOR Vy.
+            //
+            // Performs a bitwise exclusive OR on the values of Vx and Vy, then stores the result in Vx. An exclusive OR compares the corrseponding bits from two values, and if the bits are not both the same, then the corresponding bit in the result is set to 1. Otherwise, it is 0.
+            Instruction::Xor(x, y) => {
+                self.v[x as usize] ^= self.v[y as usize];
+
+                if self.quirks.logic_resets_vf {
+                    self.v[0xF] = 0;
+                }
+            }
+
+            // 8xy4 - ADD Vx, Vy
+            // Set Vx = Vx + Vy, set VF = carry.
+            //
+            // The values of Vx and Vy are added together. If the result is greater than 8 bits (i.e., > 255,) VF is set to 1, otherwise 0. Only the lowest 8 bits of the result are kept, and stored in Vx.
+            Instruction::Add(x, y) => {
+                let vx = self.v[x as usize] as u16;
+                let vy = self.v[y as usize] as u16;
+                let sum = vx + vy;
+
+                // VF is set last, so the flag wins when x is F
+                self.v[x as usize] = sum as u8;
+                self.v[0xF] = (sum > 0xFF) as u8;
+            }
+
+            // 8xy5 - SUB Vx, Vy
+            // Set Vx = Vx - Vy, set VF = NOT borrow.
+            //
+            // If Vx > Vy, then VF is set to 1, otherwise 0. Then Vy is subtracted from Vx, and the results stored in Vx.
+            Instruction::Sub(x, y) => {
+                let vx = self.v[x as usize];
+                let vy = self.v[y as usize];
+
+                // No borrow when both are equal either, which Cowgod's description leaves out
+                self.v[x as usize] = vx.wrapping_sub(vy);
+                self.v[0xF] = (vx >= vy) as u8;
+            }
+
+            // 8xy6 - SHR Vx {, Vy}
+            // Set Vx = Vx SHR 1.
+            //
+            // If the least-significant bit of Vx is 1, then VF is set to 1, otherwise 0. Then Vx is divided by 2.
+            Instruction::Shr(x, y) => {
+                let vx = if self.quirks.shift_uses_vy {
+                    self.v[y as usize]
+                } else {
+                    self.v[x as usize]
+                };
+
+                self.v[x as usize] = vx >> 1;
+                self.v[0xF] = vx & 1;
+            }
+
+            // 8xy7 - SUBN Vx, Vy
+            // Set Vx = Vy - Vx, set VF = NOT borrow.
+            //
+            // If Vy > Vx, then VF is set to 1, otherwise 0. Then Vx is subtracted from Vy, and the results stored in Vx.
+            Instruction::Subn(x, y) => {
+                let vx = self.v[x as usize];
+                let vy = self.v[y as usize];
+
+                self.v[x as usize] = vy.wrapping_sub(vx);
+                self.v[0xF] = (vy >= vx) as u8;
+            }
+
+            // 8xyE - SHL Vx {, Vy}
+            // Set Vx = Vx SHL 1.
+            //
+            // If the most-significant bit of Vx is 1, then VF is set to 1, otherwise to 0. Then Vx is multiplied by 2.
+            Instruction::Shl(x, y) => {
+                let vx = if self.quirks.shift_uses_vy {
+                    self.v[y as usize]
+                } else {
+                    self.v[x as usize]
+                };
+
+                self.v[x as usize] = vx << 1;
+                self.v[0xF] = vx >> 7;
+            }
+
+            // 9xy0 - SNE Vx, Vy
+            // Skip next instruction if Vx != Vy.
+            //
+            // The values of Vx and Vy are compared, and if they are not equal, the program counter is increased by 2.
+            Instruction::SkipNe(x, y) => {
+                let vx = self.v[x as usize];
+                let vy = self.v[y as usize];
+
+                if vx != vy {
+                    self.skip_next();
+                }
+            }
+
+            // Annn - LD I, addr
+            // Set I = nnn.
+            //
+            // The value of register I is set to nnn.
+            Instruction::LoadI(addr) => {
+                self.i = addr;
+            }
+
+            // Bnnn - JP V0, addr
+            // Jump to location nnn + V0.
+            //
+            // The program counter is set to nnn plus the value of V0.
+            Instruction::JumpV0(addr) => {
+                // SUPER-CHIP reads it as Bxnn, jumping to xnn + Vx
+                let offset = if self.quirks.jump_uses_vx {
+                    self.v[(addr >> 8) as usize]
+                } else {
+                    self.v[0]
+                };
+
+                self.pc = addr + offset as u16;
+            }
+
+            // Cxkk - RND Vx, byte
+            // Set Vx = random byte AND kk.
+            //
+            // The interpreter generates a random number from 0 to 255, which is then ANDed with the value kk. The results are stored in Vx. See instruction 8xy2 for more information on AND.
+            Instruction::Random(x, kk) => {
+                let rnd = self.rng.next_u8();
+                self.v[x as usize] = rnd & kk;
+            }
+
+            // Dxyn - DRW Vx, Vy, nibble
+            // Display n-byte sprite starting at memory location I at (Vx, Vy), set VF = collision.
+            //
+            // The interpreter reads n bytes from memory, starting at the address stored in I. These bytes are then displayed as sprites on screen at coordinates (Vx, Vy). Sprites are XORed onto the existing screen. If this causes any pixels to be erased, VF is set to 1, otherwise it is set to 0. If the sprite is positioned so part of it is outside the coordinates of the display, it wraps around to the opposite side of the screen. See instruction 8xy3 for more information on XOR, and section 2.4, Display, for more information on the Chip-8 screen and sprites.
+            //
+            // Dxy0 - DRW Vx, Vy, 0 (SUPER-CHIP)
+            // Display 16x16 sprite (two bytes per row) starting at memory location I at (Vx, Vy), set VF = collision.
+            Instruction::Draw(x, y, n) => {
+                let cols = self.width();
+                let rows = self.height();
+
+                // Starting position always wraps, only the sprite itself may be clipped
+                let vx = (self.v[x as usize] as usize % cols) as isize;
+                let vy = (self.v[y as usize] as usize % rows) as isize;
+
+                let (width, height) = match n {
+                    0 => (16isize, 16isize),
+                    n => (8isize, n as isize),
+                };
+                let row_bytes = width as usize / 8;
+
+                self.v[0xF] = 0;
+
+                // With multiple XO-CHIP planes selected, sprite data for each plane follows the previous one
+                let mut addr = self.i as usize;
+                for plane in (0..PLANES).map(|idx| 1u8 << idx) {
+                    if self.planes & plane == 0 {
+                        continue;
+                    }
+
+                    for row in 0..height {
+                        check_addr(addr + row_bytes - 1)?;
+
+                        let mut sprite = (self.read_memory(addr) as u16) << 8;
+                        if row_bytes == 2 {
+                            sprite |= self.read_memory(addr + 1) as u16;
+                        }
+                        addr += row_bytes;
+
+                        for col in 0..width {
+                            let (px, py) = (vx + col, vy + row);
+                            let clipped = self.quirks.clip_sprites
+                                && (px >= cols as isize || py >= rows as isize);
+
+                            if (sprite & 0x8000) > 0 && !clipped && self.set_pixel(px, py, plane) {
+                                self.v[0xF] = 1;
+                            }
+
+                            sprite <<= 1;
+                        }
+                    }
+                }
+
+                if self.quirks.display_wait {
+                    self.vblank_wait = true;
+                }
+            }
+
+            // Ex9E - SKP Vx
+            // Skip next instruction if key with the value of Vx is pressed.
+            //
+            // Checks the keyboard, and if the key corresponding to the value of Vx is currently in the down position, PC is increased by 2.
+            Instruction::SkipKey(x) => {
+                let vx = self.v[x as usize];
+
+                if self.is_key_pressed(vx) {
+                    self.skip_next();
+                }
+            }
+
+            // ExA1 - SKNP Vx
+            // Skip next instruction if key with the value of Vx is not pressed.
+            //
+            // Checks the keyboard, and if the key corresponding to the value of Vx is currently in the up position, PC is increased by 2.
+            Instruction::SkipNotKey(x) => {
+                let vx = self.v[x as usize];
+
+                if !self.is_key_pressed(vx) {
+                    self.skip_next();
+                }
+            }
+
+            // F000 nnnn - LD I, long addr (XO-CHIP)
+            // Set I = nnnn, the 16-bit address following this instruction.
+            Instruction::LoadLongI => {
+                let addr = check_addr(self.pc as usize + 1)? - 1;
+                self.i = ((self.memory[addr] as u16) << 8) | self.memory[addr + 1] as u16;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            // Fn01 - PLANE n (XO-CHIP)
+            // Select bitplanes n to draw, clear and scroll.
+            Instruction::Plane(x) => {
+                self.planes = x & ((1 << PLANES) - 1) as u8;
+            }
+
+            // F002 - AUDIO (XO-CHIP)
+            // Load 16 bytes audio pattern buffer from memory starting at location I.
+            Instruction::Audio => {
+                let i = check_addr(self.i as usize + 0xF)? - 0xF;
+
+                for idx in 0..self.audio_pattern.len() {
+                    self.audio_pattern[idx] = self.read_memory(i + idx);
+                }
+            }
+
+            // Fx07 - LD Vx, DT
+            // Set Vx = delay timer value.
+            //
+            // The value of DT is placed into Vx.
+            Instruction::LoadDelay(x) => {
+                self.v[x as usize] = self.dt;
+            }
+
+            // Fx0A - LD Vx, K
+            // Wait for a key press, store the value of the key in Vx.
+            //
+            // All execution stops until a key is pressed, then the value of that key is stored in Vx.
+            Instruction::WaitKey(x) => {
+                self.paused = true;
+                self.unpause_next = Some(x);
+            }
+
+            // Fx15 - LD DT, Vx
+            // Set delay timer = Vx.
+            //
+            // DT is set equal to the value of Vx.
+            Instruction::SetDelay(x) => {
+                let vx = self.v[x as usize];
+                self.dt = vx;
+            }
+
+            // Fx18 - LD ST, Vx
+            // Set sound timer = Vx.
+            //
+            // ST is set equal to the value of Vx.
+            Instruction::SetSound(x) => {
+                let vx = self.v[x as usize];
+                self.st = vx;
+            }
+
+            // Fx1E - ADD I, Vx
+            // Set I = I + Vx.
+            //
+            // The values of I and Vx are added, and the results are stored in I.
+            Instruction::AddI(x) => {
+                let vx = self.v[x as usize];
+                self.i = self.i.wrapping_add(vx as u16);
+            }
+
+            // Fx29 - LD F, Vx
+            // Set I = location of sprite for digit Vx.
+            //
+            // The value of I is set to the location for the hexadecimal sprite corresponding to the value of Vx. See section 2.4, Display, for more information on the Chip-8 hexadecimal font.
+            Instruction::Font(x) => {
+                let vx = self.v[x as usize];
+                self.i = vx as u16 * 5;
+            }
+
+            // Fx30 - LD HF, Vx (SUPER-CHIP)
+            // Set I = location of big (8x10) sprite for digit Vx.
+            Instruction::BigFont(x) => {
+                let vx = self.v[x as usize] & 0xF;
+                self.i = BIG_FONT_OFFSET as u16 + vx as u16 * 10;
+            }
+
+            // Fx33 - LD B, Vx
+            // Store BCD representation of Vx in memory locations I, I+1, and I+2.
+            //
+            // The interpreter takes the decimal value of Vx, and places the hundreds digit in memory at location in I, the tens digit at location I+1, and the ones digit at location I+2.
+            Instruction::Bcd(x) => {
+                let i = check_addr(self.i as usize + 2)? - 2;
+                let vx = self.v[x as usize];
+
+                self.write_memory(i, vx / 100);
+                self.write_memory(i + 1, (vx / 10) % 10);
+                self.write_memory(i + 2, (vx % 100) % 10);
+            }
+
+            // Fx55 - LD [I], Vx
+            // Store v V0 through Vx in memory starting at location I.
+            //
+            // The interpreter copies the values of v V0 through Vx into memory, starting at the address in I.
+            Instruction::StoreRegs(x) => {
+                let count = x as usize + 1;
+                check_addr(self.i as usize + count - 1)?;
+
+                for idx in 0..count {
+                    self.write_memory(self.i as usize + idx, self.v[idx]);
+                }
+
+                self.increment_i_after_load_store(count);
+            }
+
+            // Fx65 - LD Vx, [I]
+            // Read v V0 through Vx from memory starting at location I.
+            //
+            // The interpreter reads values from memory starting at location I into v V0 through Vx.
+            Instruction::LoadRegs(x) => {
+                let count = x as usize + 1;
+                check_addr(self.i as usize + count - 1)?;
+
+                for idx in 0..count {
+                    self.v[idx] = self.read_memory(self.i as usize + idx);
+                }
+
+                self.increment_i_after_load_store(count);
+            }
+
+            // Fx3A - PITCH Vx (XO-CHIP)
+            // Set audio pattern playback rate from Vx.
+            Instruction::Pitch(x) => {
+                self.pitch = self.v[x as usize];
+            }
+
+            // Fx75 - LD R, Vx (SUPER-CHIP)
+            // Store V0 through Vx in RPL user flags.
+            Instruction::StoreFlags(x) => {
+                let x = x as usize;
+                self.rpl[..=x].copy_from_slice(&self.v[..=x]);
+            }
+
+            // Fx85 - LD Vx, R (SUPER-CHIP)
+            // Read V0 through Vx from RPL user flags.
+            Instruction::LoadFlags(x) => {
+                let x = x as usize;
+                self.v[..=x].copy_from_slice(&self.rpl[..=x]);
+            }
+
+            Instruction::Unknown(op) => return Err(self.unknown_opcode(op)),
+        }
+
+        Ok(())
+    }
+
+    /// Builds error for given opcode, which was fetched from `pc - 2`
+    fn unknown_opcode(&self, op: u16) -> EmulatorError {
+        EmulatorError::UnknownOpcode {
+            op,
+            pc: self.pc.wrapping_sub(2),
+        }
+    }
+}
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Registers affected by XO-CHIP `5xy2`/`5xy3`, from x to y (inclusive) in either direction
+fn register_range(x: u8, y: u8) -> Vec<usize> {
+    let (x, y) = (x as usize, y as usize);
+    if x <= y {
+        (x..=y).collect()
+    } else {
+        (y..=x).rev().collect()
+    }
+}
+
+/// Makes sure given address lies within RAM
+fn check_addr(addr: usize) -> Result<usize, EmulatorError> {
+    if addr < MEMORY_SIZE {
+        Ok(addr)
+    } else {
+        Err(EmulatorError::MemoryOutOfBounds { addr })
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt;
+
+/// Errors that stop the Emulator from executing further
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmulatorError {
+    /// Opcode at `pc` does not map to any instruction
+    UnknownOpcode { op: u16, pc: u16 },
+    /// `2nnn` at `pc` was executed with all 16 stack slots in use
+    StackOverflow { pc: u16 },
+    /// `00EE` at `pc` was executed with an empty stack
+    StackUnderflow { pc: u16 },
+    /// An instruction tried to access memory past the end of RAM
+    MemoryOutOfBounds { addr: usize },
+    /// ROM does not fit in RAM after `ROM_OFFSET`
+    RomTooLarge { size: usize, max: usize },
+}
+
+impl fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmulatorError::UnknownOpcode { op, pc } => {
+                write!(f, "unknown opcode {:#06x} at {:#06x}", op, pc)
+            }
+            EmulatorError::StackOverflow { pc } => write!(f, "stack overflow at {:#06x}", pc),
+            EmulatorError::StackUnderflow { pc } => write!(f, "stack underflow at {:#06x}", pc),
+            EmulatorError::MemoryOutOfBounds { addr } => {
+                write!(f, "memory access out of bounds at {:#06x}", addr)
+            }
+            EmulatorError::RomTooLarge { size, max } => write!(
+                f,
+                "ROM is {} bytes, but at most {} bytes fit in memory",
+                size, max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EmulatorError {}
+
+/// Errors that prevent a save state from being loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// Data is not a save state
+    BadMagic,
+    /// Save state was written in a format version this build cannot read
+    UnsupportedVersion(u8),
+    /// Save state was made with a different ROM loaded
+    RomMismatch,
+    /// Save state is truncated or contains invalid values
+    Corrupted,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::BadMagic => write!(f, "not a save state"),
+            StateError::UnsupportedVersion(version) => {
+                write!(f, "unsupported save state version {}", version)
+            }
+            StateError::RomMismatch => write!(f, "save state was made with a different ROM"),
+            StateError::Corrupted => write!(f, "save state is corrupted"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+/// Error in assembly source, see `asm::assemble`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    /// Line the error is on, counted from 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
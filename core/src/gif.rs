@@ -0,0 +1,244 @@
+use std::{collections::HashMap, time::Duration};
+
+use crate::{emu::Emulator, image};
+
+/// Shortest time a frame is kept for, browsers slow down anything faster than 50 fps
+const MIN_FRAME: Duration = Duration::from_millis(20);
+
+/// Records consecutive displays into an animated GIF, looping forever.
+///
+/// The image size is set by the first frame, later frames in another resolution (e.g.
+/// after switching to SUPER-CHIP high resolution) are resized to fit. Frames are encoded
+/// as they come, only the last one is kept around, since its delay is not known yet.
+pub struct GifRecorder {
+    palette: [[u8; 3]; 4],
+    scale: usize,
+    /// Size of the image, before scaling
+    size: Option<(usize, usize)>,
+    /// Encoded file so far, without the trailer
+    out: Vec<u8>,
+    /// Last display added and for how long it was shown
+    pending: Option<(Vec<u8>, Duration)>,
+    /// Time already written out as frame delays, which are rounded to hundredths
+    written: Duration,
+    /// Total time of frames written out
+    elapsed: Duration,
+    /// Number of frames written out
+    frames: usize,
+}
+
+impl GifRecorder {
+    /// Create new recorder, colouring pixels through the palette and scaling them up by `scale`
+    pub fn new(palette: &[[u8; 3]; 4], scale: usize) -> Self {
+        Self {
+            palette: *palette,
+            scale: scale.max(1),
+            size: None,
+            out: Vec::new(),
+            pending: None,
+            written: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            frames: 0,
+        }
+    }
+
+    /// Adds current display, shown for given duration.
+    ///
+    /// Frames identical to the previous one only make it last longer. Frames replaced
+    /// before `MIN_FRAME` passed are dropped.
+    pub fn push(&mut self, emu: &Emulator, duration: Duration) {
+        let (width, height) = *self.size.get_or_insert((emu.width(), emu.height()));
+        let pixels = if (emu.width(), emu.height()) == (width, height) {
+            emu.display.clone()
+        } else {
+            image::resize(&emu.display, emu.width(), emu.height(), width, height)
+        };
+
+        match &mut self.pending {
+            Some((last, shown)) if *last == pixels => *shown += duration,
+            Some((last, shown)) if *shown < MIN_FRAME => {
+                *last = pixels;
+                *shown += duration;
+            }
+            _ => {
+                if let Some((last, shown)) = self.pending.take() {
+                    self.write_frame(&last, shown);
+                }
+                self.pending = Some((pixels, duration));
+            }
+        }
+    }
+
+    /// Number of frames recorded so far
+    pub fn len(&self) -> usize {
+        self.frames + self.pending.is_some() as usize
+    }
+
+    /// Whether nothing was recorded yet
+    pub fn is_empty(&self) -> bool {
+        self.size.is_none()
+    }
+
+    /// Writes out the last frame and returns the GIF file
+    pub fn finish(mut self) -> Vec<u8> {
+        if let Some((last, shown)) = self.pending.take() {
+            self.write_frame(&last, shown);
+        }
+
+        if self.out.is_empty() {
+            self.write_header();
+        }
+
+        self.out.push(0x3B);
+        self.out
+    }
+
+    /// Writes header, palette and the extension making the animation loop
+    fn write_header(&mut self) {
+        let (width, height) = self.size.unwrap_or((0, 0));
+        let out = &mut self.out;
+
+        out.extend_from_slice(b"GIF89a");
+        out.extend_from_slice(&((width * self.scale) as u16).to_le_bytes());
+        out.extend_from_slice(&((height * self.scale) as u16).to_le_bytes());
+        // Global colour table of 4 entries, 2 bits per primary colour
+        out.extend_from_slice(&[0x91, 0, 0]);
+        for colour in self.palette.iter() {
+            out.extend_from_slice(colour);
+        }
+
+        // NETSCAPE2.0 application extension, repeat forever
+        out.extend_from_slice(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+    }
+
+    /// Encodes a frame, its delay rounded so the total stays in sync with emulated time
+    fn write_frame(&mut self, pixels: &[u8], shown: Duration) {
+        if self.out.is_empty() {
+            self.write_header();
+        }
+
+        self.elapsed += shown;
+        let total = self.elapsed.as_millis() / 10;
+        let delay = (total - self.written.as_millis() / 10).min(u16::MAX as u128) as u16;
+        self.written += Duration::from_millis(delay as u64 * 10);
+
+        let (width, height) = self.size.unwrap();
+        let (scaled_width, scaled_height) = (width * self.scale, height * self.scale);
+        let indices: Vec<u8> = image::resize(pixels, width, height, scaled_width, scaled_height)
+            .iter()
+            .map(|px| px & 3)
+            .collect();
+        self.frames += 1;
+
+        let out = &mut self.out;
+
+        // Graphic control extension, only for the delay
+        out.extend_from_slice(&[0x21, 0xF9, 0x04, 0x00]);
+        out.extend_from_slice(&delay.to_le_bytes());
+        out.extend_from_slice(&[0x00, 0x00]);
+
+        // Image descriptor, covering the whole image
+        out.push(0x2C);
+        out.extend_from_slice(&[0, 0, 0, 0]);
+        out.extend_from_slice(&(scaled_width as u16).to_le_bytes());
+        out.extend_from_slice(&(scaled_height as u16).to_le_bytes());
+        out.push(0x00);
+
+        out.push(MIN_CODE_SIZE);
+        for block in lzw(&indices).chunks(0xFF) {
+            out.push(block.len() as u8);
+            out.extend_from_slice(block);
+        }
+        out.push(0x00);
+    }
+}
+
+/// Bits per pixel of image data, 4 colours need 2
+const MIN_CODE_SIZE: u8 = 2;
+/// Codes are at most 12 bits wide
+const MAX_CODE: u16 = 0xFFF;
+
+/// Compresses colour indices with the variable width LZW used by GIF
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << MIN_CODE_SIZE;
+    let end = clear + 1;
+
+    let mut bits = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut width = MIN_CODE_SIZE + 1;
+    let mut next = end + 1;
+
+    bits.write(clear, width);
+
+    let mut pixels = indices.iter();
+    let mut prefix = match pixels.next() {
+        Some(&px) => px as u16,
+        None => {
+            bits.write(end, width);
+            return bits.finish();
+        }
+    };
+
+    for &px in pixels {
+        if let Some(&code) = table.get(&(prefix, px)) {
+            prefix = code;
+            continue;
+        }
+
+        bits.write(prefix, width);
+
+        if next == MAX_CODE {
+            // Table is full, start over
+            bits.write(clear, width);
+            table.clear();
+            width = MIN_CODE_SIZE + 1;
+            next = end + 1;
+        } else {
+            table.insert((prefix, px), next);
+            // Decoder adds this code one step later, and widens once it no longer fits
+            if next == 1 << width {
+                width += 1;
+            }
+            next += 1;
+        }
+
+        prefix = px as u16;
+    }
+
+    bits.write(prefix, width);
+    // Decoder adds one more code after reading the last one
+    if next == 1 << width && width < 12 {
+        width += 1;
+    }
+    bits.write(end, width);
+
+    bits.finish()
+}
+
+/// Packs codes into bytes, least significant bit first
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u32,
+    len: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u8) {
+        self.acc |= (code as u32) << self.len;
+        self.len += width;
+
+        while self.len >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.out.push(self.acc as u8);
+        }
+        self.out
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt::Write;
+
+use crate::emu::Emulator;
+
+/// Key press or release scheduled by an input script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputEvent {
+    /// Frame (timer update) the event happens at, counted from 0
+    pub frame: u64,
+    /// Key on the hex keypad
+    pub key: u8,
+    /// Whether key is pressed or released
+    pub pressed: bool,
+}
+
+/// Scripted input, fed into the Emulator as frames go by.
+///
+/// Scripts are text files with one event per line: frame number, `down` or `up`, and
+/// the hex key, e.g. `120 down 5`. Empty lines and lines starting with `#` are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct InputScript {
+    events: Vec<InputEvent>,
+    /// Index of the first event not applied yet
+    next: usize,
+}
+
+impl InputScript {
+    /// Parses script text, see `InputScript` for the format
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut events = Vec::new();
+
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let event = match fields[..] {
+                [frame, action, key] => {
+                    let frame = frame.parse().ok();
+                    let pressed = match action {
+                        "down" => Some(true),
+                        "up" => Some(false),
+                        _ => None,
+                    };
+                    let key = u8::from_str_radix(key, 16).ok().filter(|key| *key < 0x10);
+
+                    match (frame, pressed, key) {
+                        (Some(frame), Some(pressed), Some(key)) => Some(InputEvent {
+                            frame,
+                            key,
+                            pressed,
+                        }),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+
+            match event {
+                Some(event) => events.push(event),
+                None => return Err(format!("invalid input event on line {}", idx + 1)),
+            }
+        }
+
+        // Keep events of the same frame in the order they were written
+        events.sort_by_key(|event| event.frame);
+
+        Ok(Self { events, next: 0 })
+    }
+
+    /// Presses and releases keys for all events due by the current frame of the Emulator
+    pub fn apply(&mut self, emu: &mut Emulator) {
+        let frame = emu.clock.timer_ticks;
+
+        while let Some(event) = self.events.get(self.next) {
+            if event.frame > frame {
+                break;
+            }
+
+            if event.pressed {
+                emu.key_down(event.key);
+            } else {
+                emu.key_up(event.key);
+            }
+            self.next += 1;
+        }
+    }
+}
+
+/// Formats registers and timers, e.g. for checking state at the end of a test run
+pub fn registers(emu: &Emulator) -> String {
+    let mut out = format!(
+        "PC={:04X} I={:04X} SP={:X} DT={:02X} ST={:02X}\n",
+        emu.pc, emu.i, emu.sp, emu.dt, emu.st
+    );
+
+    for (x, value) in emu.v.iter().enumerate() {
+        let sep = if x == 0xF { '\n' } else { ' ' };
+        write!(out, "V{:X}={:02X}{}", x, value, sep).unwrap();
+    }
+
+    out
+}
@@ -0,0 +1,159 @@
+use crate::emu::Emulator;
+
+/// Colours of pixels by XO-CHIP planes they are set in: none, plane 1, plane 2, both
+pub const PALETTE: [[u8; 3]; 4] = [
+    [0x00, 0x00, 0x00],
+    [0xFF, 0xFF, 0xFF],
+    [0xAA, 0xAA, 0xAA],
+    [0x55, 0x55, 0x55],
+];
+
+/// Renders display as text, `#` for lit pixels and `.` for dark ones, one line per row
+pub fn ascii(emu: &Emulator) -> String {
+    let mut out = String::with_capacity((emu.width() + 1) * emu.height());
+
+    for row in emu.display.chunks(emu.width()) {
+        out.extend(row.iter().map(|px| if *px != 0 { '#' } else { '.' }));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Encodes display as a plain (ASCII) PBM image, lit pixels are black
+pub fn pbm(emu: &Emulator) -> Vec<u8> {
+    let mut out = format!("P1\n{} {}\n", emu.width(), emu.height());
+
+    for row in emu.display.chunks(emu.width()) {
+        let bits: Vec<&str> = row
+            .iter()
+            .map(|px| if *px != 0 { "1" } else { "0" })
+            .collect();
+        out.push_str(&bits.join(" "));
+        out.push('\n');
+    }
+
+    out.into_bytes()
+}
+
+/// Display as RGB bytes, coloured through the palette
+pub fn rgb(emu: &Emulator, palette: &[[u8; 3]; 4]) -> Vec<u8> {
+    colour(&emu.display, palette)
+}
+
+/// Pixels (bitmasks of planes, like `Emulator::display`) as RGB bytes, coloured through the palette
+pub fn colour(pixels: &[u8], palette: &[[u8; 3]; 4]) -> Vec<u8> {
+    pixels
+        .iter()
+        .flat_map(|px| palette[*px as usize & 3])
+        .collect()
+}
+
+/// Resizes one byte per pixel image, picking the nearest pixel.
+///
+/// Scaling up by an integer factor turns every pixel into a square block.
+pub fn resize(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+    new_width: usize,
+    new_height: usize,
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(new_width * new_height);
+
+    for y in 0..new_height {
+        let row = &pixels[y * height / new_height * width..];
+        out.extend((0..new_width).map(|x| row[x * width / new_width]));
+    }
+
+    out
+}
+
+/// Encodes display as a PNG image, coloured through the palette and scaled up by `scale`
+pub fn png(emu: &Emulator, palette: &[[u8; 3]; 4], scale: usize) -> Vec<u8> {
+    let (width, height) = (emu.width() * scale, emu.height() * scale);
+    let pixels = resize(&emu.display, emu.width(), emu.height(), width, height);
+
+    encode_png(width, height, &colour(&pixels, palette))
+}
+
+/// Encodes 8-bit RGB pixels as a PNG image.
+///
+/// Image data is stored without compression, CHIP-8 screens are tiny anyway.
+pub fn encode_png(width: usize, height: usize, rgb: &[u8]) -> Vec<u8> {
+    // Every scanline starts with filter type, 0 meaning none
+    let mut raw = Vec::with_capacity((width * 3 + 1) * height);
+    for line in rgb.chunks(width * 3) {
+        raw.push(0);
+        raw.extend_from_slice(line);
+    }
+
+    // zlib stream made of stored deflate blocks, each holding at most 65535 bytes
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        zlib.push(blocks.peek().is_none() as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 bits per channel, RGB, deflate, no filtering, no interlacing
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+    write_chunk(&mut out, b"IHDR", &header);
+    write_chunk(&mut out, b"IDAT", &zlib);
+    write_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+/// Appends PNG chunk: length, type, data and CRC of type and data
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// CRC-32 (ISO-HDLC) used by PNG chunks
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+/// Adler-32 checksum ending zlib streams
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+
+    (b << 16) | a
+}
@@ -0,0 +1,336 @@
+use std::fmt;
+
+/// Single CHIP-8, SUPER-CHIP or XO-CHIP instruction, decoded from its opcode.
+///
+/// Displays in the syntax of `compiler/asm.ts`, e.g. `ld I, #754` or `drw V4, V5`.
+/// Registers are given by their index. Like there, `drw` leaves out the sprite height,
+/// listings still have it in the opcode they are annotated with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    /// 0nnn - SYS addr
+    Sys(u16),
+    /// 00E0 - CLS
+    Cls,
+    /// 00EE - RET
+    Ret,
+    /// 00Cn - SCD nibble (SUPER-CHIP)
+    ScrollDown(u8),
+    /// 00Dn - SCU nibble (XO-CHIP)
+    ScrollUp(u8),
+    /// 00FB - SCR (SUPER-CHIP)
+    ScrollRight,
+    /// 00FC - SCL (SUPER-CHIP)
+    ScrollLeft,
+    /// 00FD - EXIT (SUPER-CHIP)
+    Exit,
+    /// 00FE - LOW (SUPER-CHIP)
+    Low,
+    /// 00FF - HIGH (SUPER-CHIP)
+    High,
+    /// 1nnn - JP addr
+    Jump(u16),
+    /// 2nnn - CALL addr
+    Call(u16),
+    /// 3xkk - SE Vx, byte
+    SkipEqByte(u8, u8),
+    /// 4xkk - SNE Vx, byte
+    SkipNeByte(u8, u8),
+    /// 5xy0 - SE Vx, Vy
+    SkipEq(u8, u8),
+    /// 5xy2 - LD [I], Vx-Vy (XO-CHIP)
+    StoreRange(u8, u8),
+    /// 5xy3 - LD Vx-Vy, [I] (XO-CHIP)
+    LoadRange(u8, u8),
+    /// 6xkk - LD Vx, byte
+    LoadByte(u8, u8),
+    /// 7xkk - ADD Vx, byte
+    AddByte(u8, u8),
+    /// 8xy0 - LD Vx, Vy
+    Load(u8, u8),
+    /// 8xy1 - OR Vx, Vy
+    Or(u8, u8),
+    /// 8xy2 - AND Vx, Vy
+    And(u8, u8),
+    /// 8xy3 - XOR Vx, Vy
+    Xor(u8, u8),
+    /// 8xy4 - ADD Vx, Vy
+    Add(u8, u8),
+    /// 8xy5 - SUB Vx, Vy
+    Sub(u8, u8),
+    /// 8xy6 - SHR Vx {, Vy}
+    Shr(u8, u8),
+    /// 8xy7 - SUBN Vx, Vy
+    Subn(u8, u8),
+    /// 8xyE - SHL Vx {, Vy}
+    Shl(u8, u8),
+    /// 9xy0 - SNE Vx, Vy
+    SkipNe(u8, u8),
+    /// Annn - LD I, addr
+    LoadI(u16),
+    /// Bnnn - JP V0, addr
+    JumpV0(u16),
+    /// Cxkk - RND Vx, byte
+    Random(u8, u8),
+    /// Dxyn - DRW Vx, Vy, nibble
+    Draw(u8, u8, u8),
+    /// Ex9E - SKP Vx
+    SkipKey(u8),
+    /// ExA1 - SKNP Vx
+    SkipNotKey(u8),
+    /// F000 nnnn - LD I, long addr (XO-CHIP), the address is the word following the opcode
+    LoadLongI,
+    /// Fn01 - PLANE n (XO-CHIP)
+    Plane(u8),
+    /// F002 - AUDIO (XO-CHIP)
+    Audio,
+    /// Fx07 - LD Vx, DT
+    LoadDelay(u8),
+    /// Fx0A - LD Vx, K
+    WaitKey(u8),
+    /// Fx15 - LD DT, Vx
+    SetDelay(u8),
+    /// Fx18 - LD ST, Vx
+    SetSound(u8),
+    /// Fx1E - ADD I, Vx
+    AddI(u8),
+    /// Fx29 - LD F, Vx
+    Font(u8),
+    /// Fx30 - LD HF, Vx (SUPER-CHIP)
+    BigFont(u8),
+    /// Fx33 - LD B, Vx
+    Bcd(u8),
+    /// Fx3A - PITCH Vx (XO-CHIP)
+    Pitch(u8),
+    /// Fx55 - LD [I], Vx
+    StoreRegs(u8),
+    /// Fx65 - LD Vx, [I]
+    LoadRegs(u8),
+    /// Fx75 - LD R, Vx (SUPER-CHIP)
+    StoreFlags(u8),
+    /// Fx85 - LD Vx, R (SUPER-CHIP)
+    LoadFlags(u8),
+    /// Opcode not matching any instruction
+    Unknown(u16),
+}
+
+impl Instruction {
+    /// Decodes instruction from its opcode
+    pub fn decode(op: u16) -> Self {
+        let x = ((op & 0x0F00) >> 8) as u8;
+        let y = ((op & 0x00F0) >> 4) as u8;
+        let n = (op & 0xF) as u8;
+        let kk = (op & 0xFF) as u8;
+        let nnn = op & 0xFFF;
+
+        match op & 0xF000 {
+            0x0000 => match op {
+                0x00E0 => Instruction::Cls,
+                0x00EE => Instruction::Ret,
+                0x00FB => Instruction::ScrollRight,
+                0x00FC => Instruction::ScrollLeft,
+                0x00FD => Instruction::Exit,
+                0x00FE => Instruction::Low,
+                0x00FF => Instruction::High,
+                _ if op & 0xFFF0 == 0x00C0 => Instruction::ScrollDown(n),
+                _ if op & 0xFFF0 == 0x00D0 => Instruction::ScrollUp(n),
+                _ => Instruction::Sys(nnn),
+            },
+            0x1000 => Instruction::Jump(nnn),
+            0x2000 => Instruction::Call(nnn),
+            0x3000 => Instruction::SkipEqByte(x, kk),
+            0x4000 => Instruction::SkipNeByte(x, kk),
+            0x5000 => match n {
+                0x0 => Instruction::SkipEq(x, y),
+                0x2 => Instruction::StoreRange(x, y),
+                0x3 => Instruction::LoadRange(x, y),
+                _ => Instruction::Unknown(op),
+            },
+            0x6000 => Instruction::LoadByte(x, kk),
+            0x7000 => Instruction::AddByte(x, kk),
+            0x8000 => match n {
+                0x0 => Instruction::Load(x, y),
+                0x1 => Instruction::Or(x, y),
+                0x2 => Instruction::And(x, y),
+                0x3 => Instruction::Xor(x, y),
+                0x4 => Instruction::Add(x, y),
+                0x5 => Instruction::Sub(x, y),
+                0x6 => Instruction::Shr(x, y),
+                0x7 => Instruction::Subn(x, y),
+                0xE => Instruction::Shl(x, y),
+                _ => Instruction::Unknown(op),
+            },
+            0x9000 if n == 0 => Instruction::SkipNe(x, y),
+            0xA000 => Instruction::LoadI(nnn),
+            0xB000 => Instruction::JumpV0(nnn),
+            0xC000 => Instruction::Random(x, kk),
+            0xD000 => Instruction::Draw(x, y, n),
+            0xE000 => match kk {
+                0x9E => Instruction::SkipKey(x),
+                0xA1 => Instruction::SkipNotKey(x),
+                _ => Instruction::Unknown(op),
+            },
+            0xF000 => match kk {
+                0x00 if x == 0 => Instruction::LoadLongI,
+                0x01 => Instruction::Plane(x),
+                0x02 if x == 0 => Instruction::Audio,
+                0x07 => Instruction::LoadDelay(x),
+                0x0A => Instruction::WaitKey(x),
+                0x15 => Instruction::SetDelay(x),
+                0x18 => Instruction::SetSound(x),
+                0x1E => Instruction::AddI(x),
+                0x29 => Instruction::Font(x),
+                0x30 => Instruction::BigFont(x),
+                0x33 => Instruction::Bcd(x),
+                0x3A => Instruction::Pitch(x),
+                0x55 => Instruction::StoreRegs(x),
+                0x65 => Instruction::LoadRegs(x),
+                0x75 => Instruction::StoreFlags(x),
+                0x85 => Instruction::LoadFlags(x),
+                _ => Instruction::Unknown(op),
+            },
+            _ => Instruction::Unknown(op),
+        }
+    }
+
+    /// Encodes instruction back into its opcode, inverse of `decode`
+    pub fn encode(&self) -> u16 {
+        let xy = |op: u16, x: u8, y: u8| op | (x as u16 & 0xF) << 8 | (y as u16 & 0xF) << 4;
+        let xkk = |op: u16, x: u8, kk: u8| op | (x as u16 & 0xF) << 8 | kk as u16;
+        let x = |op: u16, x: u8| op | (x as u16 & 0xF) << 8;
+
+        match *self {
+            Instruction::Sys(addr) => addr & 0xFFF,
+            Instruction::Cls => 0x00E0,
+            Instruction::Ret => 0x00EE,
+            Instruction::ScrollDown(n) => 0x00C0 | (n as u16 & 0xF),
+            Instruction::ScrollUp(n) => 0x00D0 | (n as u16 & 0xF),
+            Instruction::ScrollRight => 0x00FB,
+            Instruction::ScrollLeft => 0x00FC,
+            Instruction::Exit => 0x00FD,
+            Instruction::Low => 0x00FE,
+            Instruction::High => 0x00FF,
+            Instruction::Jump(addr) => 0x1000 | (addr & 0xFFF),
+            Instruction::Call(addr) => 0x2000 | (addr & 0xFFF),
+            Instruction::SkipEqByte(vx, kk) => xkk(0x3000, vx, kk),
+            Instruction::SkipNeByte(vx, kk) => xkk(0x4000, vx, kk),
+            Instruction::SkipEq(vx, vy) => xy(0x5000, vx, vy),
+            Instruction::StoreRange(vx, vy) => xy(0x5002, vx, vy),
+            Instruction::LoadRange(vx, vy) => xy(0x5003, vx, vy),
+            Instruction::LoadByte(vx, kk) => xkk(0x6000, vx, kk),
+            Instruction::AddByte(vx, kk) => xkk(0x7000, vx, kk),
+            Instruction::Load(vx, vy) => xy(0x8000, vx, vy),
+            Instruction::Or(vx, vy) => xy(0x8001, vx, vy),
+            Instruction::And(vx, vy) => xy(0x8002, vx, vy),
+            Instruction::Xor(vx, vy) => xy(0x8003, vx, vy),
+            Instruction::Add(vx, vy) => xy(0x8004, vx, vy),
+            Instruction::Sub(vx, vy) => xy(0x8005, vx, vy),
+            Instruction::Shr(vx, vy) => xy(0x8006, vx, vy),
+            Instruction::Subn(vx, vy) => xy(0x8007, vx, vy),
+            Instruction::Shl(vx, vy) => xy(0x800E, vx, vy),
+            Instruction::SkipNe(vx, vy) => xy(0x9000, vx, vy),
+            Instruction::LoadI(addr) => 0xA000 | (addr & 0xFFF),
+            Instruction::JumpV0(addr) => 0xB000 | (addr & 0xFFF),
+            Instruction::Random(vx, kk) => xkk(0xC000, vx, kk),
+            Instruction::Draw(vx, vy, n) => xy(0xD000, vx, vy) | (n as u16 & 0xF),
+            Instruction::SkipKey(vx) => x(0xE09E, vx),
+            Instruction::SkipNotKey(vx) => x(0xE0A1, vx),
+            Instruction::LoadLongI => 0xF000,
+            Instruction::Plane(n) => x(0xF001, n),
+            Instruction::Audio => 0xF002,
+            Instruction::LoadDelay(vx) => x(0xF007, vx),
+            Instruction::WaitKey(vx) => x(0xF00A, vx),
+            Instruction::SetDelay(vx) => x(0xF015, vx),
+            Instruction::SetSound(vx) => x(0xF018, vx),
+            Instruction::AddI(vx) => x(0xF01E, vx),
+            Instruction::Font(vx) => x(0xF029, vx),
+            Instruction::BigFont(vx) => x(0xF030, vx),
+            Instruction::Bcd(vx) => x(0xF033, vx),
+            Instruction::Pitch(vx) => x(0xF03A, vx),
+            Instruction::StoreRegs(vx) => x(0xF055, vx),
+            Instruction::LoadRegs(vx) => x(0xF065, vx),
+            Instruction::StoreFlags(vx) => x(0xF075, vx),
+            Instruction::LoadFlags(vx) => x(0xF085, vx),
+            Instruction::Unknown(op) => op,
+        }
+    }
+
+    /// Size of the instruction in memory, in bytes
+    pub fn size(&self) -> u16 {
+        match self {
+            Instruction::LoadLongI => 4,
+            _ => 2,
+        }
+    }
+
+    /// Whether the instruction may skip the one following it
+    pub fn is_skip(&self) -> bool {
+        matches!(
+            self,
+            Instruction::SkipEqByte(..)
+                | Instruction::SkipNeByte(..)
+                | Instruction::SkipEq(..)
+                | Instruction::SkipNe(..)
+                | Instruction::SkipKey(_)
+                | Instruction::SkipNotKey(_)
+        )
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Instruction::Sys(addr) => write!(f, "sys #0x{:04x}", addr),
+            Instruction::Cls => write!(f, "cls"),
+            Instruction::Ret => write!(f, "ret"),
+            Instruction::ScrollDown(n) => write!(f, "scd #{}", n),
+            Instruction::ScrollUp(n) => write!(f, "scu #{}", n),
+            Instruction::ScrollRight => write!(f, "scr"),
+            Instruction::ScrollLeft => write!(f, "scl"),
+            Instruction::Exit => write!(f, "exit"),
+            Instruction::Low => write!(f, "low"),
+            Instruction::High => write!(f, "high"),
+            Instruction::Jump(addr) => write!(f, "jmp #0x{:04x}", addr),
+            Instruction::Call(addr) => write!(f, "call #0x{:04x}", addr),
+            Instruction::SkipEqByte(x, kk) => write!(f, "se V{:x}, #{}", x, kk),
+            Instruction::SkipNeByte(x, kk) => write!(f, "sne V{:x}, #{}", x, kk),
+            Instruction::SkipEq(x, y) => write!(f, "se V{:x}, V{:x}", x, y),
+            Instruction::StoreRange(x, y) => write!(f, "ld I, V{:x}-V{:x}", x, y),
+            Instruction::LoadRange(x, y) => write!(f, "ld V{:x}-V{:x}, I", x, y),
+            Instruction::LoadByte(x, kk) => write!(f, "ld V{:x}, #{}", x, kk),
+            Instruction::AddByte(x, kk) => write!(f, "add V{:x}, #{}", x, kk),
+            Instruction::Load(x, y) => write!(f, "ld V{:x}, V{:x}", x, y),
+            Instruction::Or(x, y) => write!(f, "or V{:x}, V{:x}", x, y),
+            Instruction::And(x, y) => write!(f, "and V{:x}, V{:x}", x, y),
+            Instruction::Xor(x, y) => write!(f, "xor V{:x}, V{:x}", x, y),
+            Instruction::Add(x, y) => write!(f, "add V{:x}, V{:x}", x, y),
+            Instruction::Sub(x, y) => write!(f, "sub V{:x}, V{:x}", x, y),
+            Instruction::Shr(x, y) => write!(f, "shr V{:x}, V{:x}", x, y),
+            Instruction::Subn(x, y) => write!(f, "subn V{:x}, V{:x}", x, y),
+            Instruction::Shl(x, y) => write!(f, "shl V{:x}, V{:x}", x, y),
+            Instruction::SkipNe(x, y) => write!(f, "sne V{:x}, V{:x}", x, y),
+            Instruction::LoadI(addr) => write!(f, "ld I, #{}", addr),
+            Instruction::JumpV0(addr) => write!(f, "jmp #0x{:04x}+V0", addr),
+            Instruction::Random(x, kk) => write!(f, "rnd V{:x}, #{}", x, kk),
+            Instruction::Draw(x, y, _) => write!(f, "drw V{:x}, V{:x}", x, y),
+            Instruction::SkipKey(x) => write!(f, "skp V{:x}", x),
+            Instruction::SkipNotKey(x) => write!(f, "sknp V{:x}", x),
+            Instruction::LoadLongI => write!(f, "ld I, long"),
+            Instruction::Plane(n) => write!(f, "plane #{}", n),
+            Instruction::Audio => write!(f, "audio"),
+            Instruction::LoadDelay(x) => write!(f, "ld V{:x}, DT", x),
+            Instruction::WaitKey(x) => write!(f, "ld V{:x}, K", x),
+            Instruction::SetDelay(x) => write!(f, "ld DT, V{:x}", x),
+            Instruction::SetSound(x) => write!(f, "ld ST, V{:x}", x),
+            Instruction::AddI(x) => write!(f, "add I, V{:x}", x),
+            Instruction::Font(x) => write!(f, "ld I, V{:x}.F", x),
+            Instruction::BigFont(x) => write!(f, "ld I, V{:x}.HF", x),
+            Instruction::Bcd(x) => write!(f, "ld I, V{:x}.B", x),
+            Instruction::Pitch(x) => write!(f, "pitch V{:x}", x),
+            Instruction::StoreRegs(x) => write!(f, "ld I, V{:x}", x),
+            Instruction::LoadRegs(x) => write!(f, "ld V{:x}, I", x),
+            Instruction::StoreFlags(x) => write!(f, "ld R, V{:x}", x),
+            Instruction::LoadFlags(x) => write!(f, "ld V{:x}, R", x),
+            Instruction::Unknown(op) => write!(f, "bad 0x{:04x}", op),
+        }
+    }
+}
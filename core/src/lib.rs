@@ -0,0 +1,24 @@
+//! CHIP-8, SUPER-CHIP and XO-CHIP emulator core, free of any windowing or audio device code.
+//!
+//! Frontends drive an `emu::Emulator` with `run_for`, feed it key presses and draw its `display`.
+
+pub mod asm;
+pub mod audio;
+pub mod clock;
+pub mod conformance;
+pub mod debugger;
+pub mod disasm;
+pub mod emu;
+pub mod error;
+pub mod gif;
+pub mod headless;
+pub mod image;
+pub mod instruction;
+pub mod movie;
+pub mod quirks;
+pub mod rewind;
+pub mod rng;
+pub mod state;
+pub mod theme;
+pub mod trace;
+pub mod watch;
@@ -0,0 +1,311 @@
+use std::{fmt::Write, time::Duration};
+
+use crate::{
+    clock::Clock,
+    emu::{Emulator, StepOutcome},
+    error::EmulatorError,
+    quirks::Quirks,
+};
+
+/// First line of every movie file
+const MAGIC: &str = "chip8 movie 1";
+
+/// Key press or release captured in a movie
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MovieEvent {
+    /// Frame (timer update) the event happened in, counted from 0
+    pub frame: u64,
+    /// Emulated nanoseconds from the start of the frame, so it lands between the same instructions
+    pub offset: u64,
+    /// Key on the hex keypad
+    pub key: u8,
+    /// Whether key was pressed or released
+    pub pressed: bool,
+}
+
+impl MovieEvent {
+    /// Emulated time the event happened at
+    pub fn time(&self) -> u64 {
+        Clock::timer_at(self.frame) + self.offset
+    }
+}
+
+/// Input of a play session, along with everything else needed to replay it exactly.
+///
+/// While set as `Emulator::movie`, every `key_down`/`key_up` that changes a key is appended.
+/// Movies are text files, a header followed by one event per line: frame, nanoseconds into
+/// the frame, `down` or `up`, and the hex key:
+///
+/// ```text
+/// chip8 movie 1
+/// rom 5a8d2b3c9e0f1a24
+/// seed 5
+/// ips 600
+/// quirks shift_uses_vy load_store_increments_i logic_resets_vf clip_sprites display_wait
+/// 120 4166666 down 5
+/// 131 0 up 5
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    /// Hash of the ROM played, see `state::rom_hash`
+    pub rom_hash: u64,
+    /// Seed of the random number generator
+    pub seed: u64,
+    /// Emulator speed
+    pub ips: u32,
+    pub quirks: Quirks,
+    pub events: Vec<MovieEvent>,
+}
+
+impl Movie {
+    /// Starts an empty movie for the Emulator, which should have just loaded its ROM
+    pub fn new(emu: &Emulator) -> Self {
+        Self {
+            rom_hash: emu.rom_hash,
+            seed: emu.seed,
+            ips: emu.ips,
+            quirks: emu.quirks,
+            events: Vec::new(),
+        }
+    }
+
+    /// Appends key event, happening at the Emulator's current time
+    pub fn record(&mut self, clock: &Clock, key: u8, pressed: bool) {
+        self.events.push(MovieEvent {
+            frame: clock.timer_ticks,
+            offset: clock.now - Clock::timer_at(clock.timer_ticks),
+            key,
+            pressed,
+        });
+    }
+
+    /// Checks that the movie was recorded on the ROM loaded into the Emulator
+    pub fn check_rom(&self, emu: &Emulator) -> Result<(), String> {
+        if self.rom_hash != emu.rom_hash {
+            return Err(format!(
+                "movie was recorded with a different ROM (hash {:016x}, loaded {:016x})",
+                self.rom_hash, emu.rom_hash
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parses movie file, see `Movie` for the format
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line.trim()));
+
+        match lines.next() {
+            Some((_, MAGIC)) => {}
+            _ => return Err(format!("not a movie, expected '{}' on line 1", MAGIC)),
+        }
+
+        let (mut rom_hash, mut seed, mut ips, mut quirks) = (None, None, None, None);
+        let mut events: Vec<MovieEvent> = Vec::new();
+
+        for (num, line) in lines {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let ok = match fields[..] {
+                ["rom", hash] => {
+                    rom_hash = u64::from_str_radix(hash, 16).ok();
+                    rom_hash.is_some()
+                }
+                ["seed", value] => {
+                    seed = value.parse().ok();
+                    seed.is_some()
+                }
+                ["ips", value] => {
+                    ips = value.parse().ok().filter(|ips| *ips > 0);
+                    ips.is_some()
+                }
+                ["quirks", ref names @ ..] => {
+                    quirks = parse_quirks(names);
+                    quirks.is_some()
+                }
+                [frame, offset, action, key] => match parse_event(frame, offset, action, key) {
+                    Some(event) if events.last().is_none_or(|last| last.time() <= event.time()) => {
+                        events.push(event);
+                        true
+                    }
+                    _ => false,
+                },
+                _ => false,
+            };
+
+            if !ok {
+                return Err(format!("invalid movie line {}: '{}'", num, line));
+            }
+        }
+
+        match (rom_hash, seed, ips, quirks) {
+            (Some(rom_hash), Some(seed), Some(ips), Some(quirks)) => Ok(Self {
+                rom_hash,
+                seed,
+                ips,
+                quirks,
+                events,
+            }),
+            _ => Err("movie header needs rom, seed, ips and quirks".to_string()),
+        }
+    }
+
+    /// Writes movie file, see `Movie` for the format
+    pub fn to_text(&self) -> String {
+        let mut out = format!(
+            "{}\nrom {:016x}\nseed {}\nips {}\nquirks",
+            MAGIC, self.rom_hash, self.seed, self.ips
+        );
+
+        for (name, enabled) in QUIRK_NAMES.iter().zip(quirk_flags(&self.quirks)) {
+            if enabled {
+                write!(out, " {}", name).unwrap();
+            }
+        }
+        out.push('\n');
+
+        for event in &self.events {
+            let action = if event.pressed { "down" } else { "up" };
+            writeln!(
+                out,
+                "{} {} {} {:X}",
+                event.frame, event.offset, action, event.key
+            )
+            .unwrap();
+        }
+
+        out
+    }
+}
+
+/// Feeds a movie's events back into the Emulator at the exact emulated time they were recorded
+#[derive(Debug, Clone)]
+pub struct Playback {
+    events: Vec<MovieEvent>,
+    /// Index of the first event not applied yet
+    next: usize,
+}
+
+impl Playback {
+    pub fn new(movie: &Movie) -> Self {
+        Self {
+            events: movie.events.clone(),
+            next: 0,
+        }
+    }
+
+    /// Whether all events were applied
+    pub fn is_finished(&self) -> bool {
+        self.next == self.events.len()
+    }
+
+    /// Presses and releases keys for all events due by the Emulator's current time
+    pub fn apply(&mut self, emu: &mut Emulator) {
+        while let Some(event) = self.events.get(self.next) {
+            if event.time() > emu.clock.now {
+                break;
+            }
+
+            if event.pressed {
+                emu.key_down(event.key);
+            } else {
+                emu.key_up(event.key);
+            }
+            self.next += 1;
+        }
+    }
+
+    /// Runs the Emulator for given amount of emulated time through `run` (e.g. `Emulator::run_for`),
+    /// split up so every event is applied exactly when it is due.
+    ///
+    /// Stops early whenever `run` does, e.g. on a breakpoint.
+    pub fn run_for<F>(
+        &mut self,
+        emu: &mut Emulator,
+        duration: Duration,
+        mut run: F,
+    ) -> Result<StepOutcome, EmulatorError>
+    where
+        F: FnMut(&mut Emulator, Duration) -> Result<StepOutcome, EmulatorError>,
+    {
+        let end = emu.clock.now + duration.as_nanos() as u64;
+
+        loop {
+            self.apply(emu);
+
+            let until = match self.events.get(self.next) {
+                Some(event) => event.time().min(end),
+                None => end,
+            };
+            let outcome = run(emu, Duration::from_nanos(until - emu.clock.now))?;
+
+            if emu.clock.now != until || outcome.is_stop() || until == end {
+                return Ok(outcome);
+            }
+        }
+    }
+}
+
+/// Names of quirks in movie files, in the order of `quirk_flags`
+const QUIRK_NAMES: [&str; 7] = [
+    "shift_uses_vy",
+    "load_store_increments_i",
+    "load_store_increments_i_by_x",
+    "jump_uses_vx",
+    "logic_resets_vf",
+    "clip_sprites",
+    "display_wait",
+];
+
+/// Whether each quirk of `QUIRK_NAMES` is enabled
+fn quirk_flags(quirks: &Quirks) -> [bool; 7] {
+    [
+        quirks.shift_uses_vy,
+        quirks.load_store_increments_i,
+        quirks.load_store_increments_i_by_x,
+        quirks.jump_uses_vx,
+        quirks.logic_resets_vf,
+        quirks.clip_sprites,
+        quirks.display_wait,
+    ]
+}
+
+/// Quirks with the given ones enabled and all others disabled
+fn parse_quirks(names: &[&str]) -> Option<Quirks> {
+    if !names.iter().all(|name| QUIRK_NAMES.contains(name)) {
+        return None;
+    }
+
+    let [shift_uses_vy, load_store_increments_i, load_store_increments_i_by_x, jump_uses_vx, logic_resets_vf, clip_sprites, display_wait] =
+        QUIRK_NAMES.map(|name| names.contains(&name));
+
+    Some(Quirks {
+        shift_uses_vy,
+        load_store_increments_i,
+        load_store_increments_i_by_x,
+        jump_uses_vx,
+        logic_resets_vf,
+        clip_sprites,
+        display_wait,
+    })
+}
+
+fn parse_event(frame: &str, offset: &str, action: &str, key: &str) -> Option<MovieEvent> {
+    let pressed = match action {
+        "down" => true,
+        "up" => false,
+        _ => return None,
+    };
+
+    Some(MovieEvent {
+        frame: frame.parse().ok()?,
+        offset: offset.parse().ok()?,
+        key: u8::from_str_radix(key, 16).ok().filter(|key| *key < 0x10)?,
+        pressed,
+    })
+}
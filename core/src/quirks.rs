@@ -0,0 +1,110 @@
+/// Behaviour of instructions that differ between CHIP-8 interpreters.
+///
+/// See https://github.com/Timendus/chip8-test-suite#quirks-test for details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// `8xy6`/`8xyE` shift Vy and store the result in Vx, instead of shifting Vx in place
+    pub shift_uses_vy: bool,
+    /// `Fx55`/`Fx65` leave `I` pointing right after the last register stored/loaded
+    pub load_store_increments_i: bool,
+    /// With `load_store_increments_i`, `I` is left on the last register instead of after it
+    pub load_store_increments_i_by_x: bool,
+    /// `Bnnn` jumps to `nnn + Vx` (x being the highest nibble of nnn) instead of `nnn + V0`
+    pub jump_uses_vx: bool,
+    /// `8xy1`/`8xy2`/`8xy3` reset VF to 0
+    pub logic_resets_vf: bool,
+    /// Sprites are clipped at the edges of the screen instead of wrapping around
+    pub clip_sprites: bool,
+    /// `Dxyn` waits for the next timer update (vertical blank) before executing further
+    pub display_wait: bool,
+}
+
+impl Quirks {
+    /// Behaviour described by Cowgod's Chip-8 Technical Reference, and the one of this
+    /// emulator before quirks could be configured
+    pub fn cowgod() -> Self {
+        Self {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            load_store_increments_i_by_x: false,
+            jump_uses_vx: false,
+            logic_resets_vf: false,
+            clip_sprites: false,
+            display_wait: false,
+        }
+    }
+
+    /// Original COSMAC VIP interpreter
+    pub fn cosmac_vip() -> Self {
+        Self {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            load_store_increments_i_by_x: false,
+            jump_uses_vx: false,
+            logic_resets_vf: true,
+            clip_sprites: true,
+            display_wait: true,
+        }
+    }
+
+    /// CHIP-48 interpreter for HP-48 calculators, whose `Fx55`/`Fx65` increment `I` by one
+    /// less than on the VIP
+    pub fn chip48() -> Self {
+        Self {
+            shift_uses_vy: false,
+            load_store_increments_i: true,
+            load_store_increments_i_by_x: true,
+            jump_uses_vx: true,
+            logic_resets_vf: false,
+            clip_sprites: true,
+            display_wait: false,
+        }
+    }
+
+    /// SUPER-CHIP 1.1 interpreter
+    pub fn super_chip() -> Self {
+        Self {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            load_store_increments_i_by_x: false,
+            jump_uses_vx: true,
+            logic_resets_vf: false,
+            clip_sprites: true,
+            display_wait: false,
+        }
+    }
+
+    /// XO-CHIP, as implemented by Octo
+    pub fn xo_chip() -> Self {
+        Self {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            load_store_increments_i_by_x: false,
+            jump_uses_vx: false,
+            logic_resets_vf: false,
+            clip_sprites: false,
+            display_wait: false,
+        }
+    }
+
+    /// Names accepted by `from_name`
+    pub const NAMES: &'static [&'static str] = &["cowgod", "vip", "chip48", "schip", "xochip"];
+
+    /// Gets preset by its name, see `NAMES`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cowgod" => Some(Self::cowgod()),
+            "vip" => Some(Self::cosmac_vip()),
+            "chip48" => Some(Self::chip48()),
+            "schip" => Some(Self::super_chip()),
+            "xochip" => Some(Self::xo_chip()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self::cowgod()
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+
+use crate::{emu::Emulator, error::StateError};
+
+/// Ring buffer of per-frame save states, used to step gameplay backwards.
+///
+/// Only the newest snapshot is kept in full. Every older frame is stored as a delta
+/// against the frame after it: run-length encoded XOR of both snapshots. Since most
+/// of `memory` and `display` does not change between frames, deltas are mostly
+/// a handful of bytes.
+pub struct Rewind {
+    /// Maximum number of frames that can be rewound
+    capacity: usize,
+    /// Full snapshot of the newest frame
+    latest: Option<Vec<u8>>,
+    /// Deltas turning a snapshot into the one of the frame before it, newest at the back
+    deltas: VecDeque<Vec<u8>>,
+}
+
+impl Rewind {
+    /// Create new rewind buffer holding up to `capacity` frames
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            latest: None,
+            deltas: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Number of frames that can currently be rewound
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    /// Whether there is no frame to rewind to
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    /// Forgets all recorded frames
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+    }
+
+    /// Records current state of the Emulator as the newest frame
+    pub fn push(&mut self, emu: &Emulator) {
+        let snapshot = emu.save_state();
+
+        if let Some(latest) = self.latest.take() {
+            if self.deltas.len() == self.capacity {
+                self.deltas.pop_front();
+            }
+
+            if self.capacity > 0 {
+                self.deltas.push_back(encode_delta(&snapshot, &latest));
+            }
+        }
+
+        self.latest = Some(snapshot);
+    }
+
+    /// Restores the frame before the newest one and drops the newest.
+    ///
+    /// Returns `false` without touching the Emulator if there is nothing to rewind. On error,
+    /// neither the Emulator nor the buffer change.
+    pub fn rewind(&mut self, emu: &mut Emulator) -> Result<bool, StateError> {
+        let (delta, latest) = match (self.deltas.back(), self.latest.as_mut()) {
+            (Some(delta), Some(latest)) => (delta, latest),
+            _ => return Ok(false),
+        };
+
+        // Only step back once loaded, so the buffer keeps matching the Emulator
+        let previous = apply_delta(latest, delta);
+        emu.load_state(&previous)?;
+        *latest = previous;
+        self.deltas.pop_back();
+        Ok(true)
+    }
+}
+
+/// Encodes delta turning `from` into `to`.
+///
+/// Layout: length of `to` (u32 LE), then repeated `(zeros, literals)` pairs of XORed bytes,
+/// where `zeros` is a count of unchanged bytes and `literals` a count of XORed bytes that follow.
+/// Counts are LEB128 encoded.
+fn encode_delta(from: &[u8], to: &[u8]) -> Vec<u8> {
+    let len = from.len().max(to.len());
+    let xor = |idx: usize| from.get(idx).unwrap_or(&0) ^ to.get(idx).unwrap_or(&0);
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&(to.len() as u32).to_le_bytes());
+
+    let mut idx = 0;
+    while idx < len {
+        let start = idx;
+        while idx < len && xor(idx) == 0 {
+            idx += 1;
+        }
+        write_count(&mut out, idx - start);
+
+        let start = idx;
+        while idx < len && xor(idx) != 0 {
+            idx += 1;
+        }
+        write_count(&mut out, idx - start);
+        out.extend((start..idx).map(xor));
+    }
+
+    out
+}
+
+/// Applies delta made by `encode_delta` to `from`, giving back `to`
+fn apply_delta(from: &[u8], delta: &[u8]) -> Vec<u8> {
+    let mut len = [0; 4];
+    len.copy_from_slice(&delta[..4]);
+    let len = u32::from_le_bytes(len) as usize;
+
+    let mut out = from.to_vec();
+    out.resize(out.len().max(len), 0);
+
+    let mut pos = 4;
+    let mut idx = 0;
+    while pos < delta.len() {
+        idx += read_count(delta, &mut pos);
+
+        let literals = read_count(delta, &mut pos);
+        for byte in &delta[pos..pos + literals] {
+            out[idx] ^= byte;
+            idx += 1;
+        }
+        pos += literals;
+    }
+
+    out.truncate(len);
+    out
+}
+
+/// Writes LEB128 encoded count
+fn write_count(out: &mut Vec<u8>, mut count: usize) {
+    while count >= 0x80 {
+        out.push((count as u8 & 0x7F) | 0x80);
+        count >>= 7;
+    }
+    out.push(count as u8);
+}
+
+/// Reads LEB128 encoded count
+fn read_count(data: &[u8], pos: &mut usize) -> usize {
+    let mut count = 0;
+    let mut shift = 0;
+
+    loop {
+        let byte = data[*pos];
+        *pos += 1;
+        count |= ((byte & 0x7F) as usize) << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            return count;
+        }
+    }
+}
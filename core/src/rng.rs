@@ -0,0 +1,46 @@
+/// Small seedable pseudo random number generator (xorshift64*) used by `Cxkk`.
+///
+/// Its whole state is a single `u64`, so it is cheap to copy and to store in save states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Create new generator from given seed. Same seed always yields the same sequence
+    pub fn new(seed: u64) -> Self {
+        // Scramble seed (SplitMix64), so that similar seeds give unrelated sequences
+        let mut z = seed.wrapping_add(0x9E3779B97F4A7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^= z >> 31;
+
+        // xorshift gets stuck at 0
+        Self::from_state(z)
+    }
+
+    /// Restore generator from a value returned by `state`
+    pub fn from_state(state: u64) -> Self {
+        Self {
+            state: if state == 0 { 1 } else { state },
+        }
+    }
+
+    /// Current internal state
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    /// Generates next random 64-bit number
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    /// Generates next random byte
+    pub fn next_u8(&mut self) -> u8 {
+        (self.next_u64() >> 56) as u8
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{
+    clock::Clock,
+    emu::{Emulator, COLS, HIRES_COLS, HIRES_ROWS, MEMORY_SIZE, PLANES, ROWS},
+    error::StateError,
+    rng::Rng,
+};
+
+/// Magic bytes every save state starts with
+const MAGIC: &[u8; 4] = b"C8ST";
+/// Version of the save state format, bumped whenever the layout changes
+const VERSION: u8 = 3;
+
+/// Hashes ROM contents (64-bit FNV-1a), used to make sure save states are loaded for the same ROM
+pub fn rom_hash(rom: &[u8]) -> u64 {
+    rom.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+impl Emulator {
+    /// Serializes full machine state into a versioned binary snapshot.
+    ///
+    /// Layout: magic, version, ROM hash, then all fields in declaration order, little endian.
+    /// Configuration (`ips`, `quirks`) is not part of the snapshot.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(MEMORY_SIZE + self.display.len() + 0x100);
+
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&self.rom_hash.to_le_bytes());
+
+        out.extend_from_slice(&self.memory);
+        out.extend_from_slice(&self.v);
+        for addr in self.stack {
+            out.extend_from_slice(&addr.to_le_bytes());
+        }
+        out.push(self.sp);
+        out.push(self.hires as u8);
+        out.extend_from_slice(&self.display);
+        out.push(self.planes);
+        out.extend_from_slice(&self.audio_pattern);
+        out.push(self.pitch);
+        out.extend_from_slice(&self.rpl);
+        out.push(self.exited as u8);
+        out.extend(self.keyboard.iter().map(|key| *key as u8));
+        out.push(self.paused as u8);
+        match self.unpause_next {
+            Some(x) => out.extend_from_slice(&[1, x]),
+            None => out.extend_from_slice(&[0, 0]),
+        }
+        out.extend_from_slice(&self.pc.to_le_bytes());
+        out.extend_from_slice(&self.i.to_le_bytes());
+        out.push(self.dt);
+        out.push(self.st);
+        out.push(self.vblank_wait as u8);
+        out.extend_from_slice(&self.rng.state().to_le_bytes());
+        for value in [
+            self.clock.now,
+            self.clock.timer_ticks,
+            self.clock.next_instruction,
+            self.clock.remainder,
+        ] {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+
+        out
+    }
+
+    /// Restores machine state from a snapshot made by `save_state`.
+    ///
+    /// Snapshot must have been made with the same ROM loaded. On error, state is left untouched.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut reader = Reader { data, pos: 0 };
+
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(StateError::BadMagic);
+        }
+
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+
+        let hash = reader.u64()?;
+        if hash != self.rom_hash {
+            return Err(StateError::RomMismatch);
+        }
+
+        let mut emu = self.clone();
+
+        emu.memory.copy_from_slice(reader.bytes(MEMORY_SIZE)?);
+        emu.v.copy_from_slice(reader.bytes(0x10)?);
+        for addr in emu.stack.iter_mut() {
+            *addr = reader.u16()?;
+        }
+        emu.sp = reader.u8()?;
+        emu.hires = reader.bool()?;
+        let pixels = if emu.hires {
+            HIRES_COLS * HIRES_ROWS
+        } else {
+            COLS * ROWS
+        };
+        emu.display = reader.bytes(pixels)?.to_vec();
+        emu.planes = reader.u8()?;
+        emu.audio_pattern.copy_from_slice(reader.bytes(0x10)?);
+        emu.pitch = reader.u8()?;
+        emu.rpl.copy_from_slice(reader.bytes(0x10)?);
+        emu.exited = reader.bool()?;
+        for key in emu.keyboard.iter_mut() {
+            *key = reader.bool()?;
+        }
+        emu.paused = reader.bool()?;
+        let waiting = reader.bool()?;
+        emu.unpause_next = match (waiting, reader.u8()?) {
+            (true, x) if x < 0x10 => Some(x),
+            (false, 0) => None,
+            _ => return Err(StateError::Corrupted),
+        };
+        emu.pc = reader.u16()?;
+        emu.i = reader.u16()?;
+        emu.dt = reader.u8()?;
+        emu.st = reader.u8()?;
+        emu.vblank_wait = reader.bool()?;
+        emu.rng = Rng::from_state(reader.u64()?);
+        emu.clock = Clock {
+            now: reader.u64()?,
+            timer_ticks: reader.u64()?,
+            next_instruction: reader.u64()?,
+            remainder: reader.u64()?,
+        };
+
+        // Bits of pixels and `planes` outside of the existing planes
+        let no_plane = !((1 << PLANES) - 1) as u8;
+
+        if reader.pos != data.len()
+            || emu.sp as usize > emu.stack.len()
+            || emu.display.iter().any(|px| px & no_plane != 0)
+            || emu.planes & no_plane != 0
+            || emu.paused != emu.unpause_next.is_some()
+            || !emu.clock.is_valid()
+        {
+            return Err(StateError::Corrupted);
+        }
+
+        *self = emu;
+        self.invalidate_decode_cache();
+        Ok(())
+    }
+}
+
+/// Reads values from a save state, failing if it ends early
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or(StateError::Corrupted)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, StateError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(StateError::Corrupted),
+        }
+    }
+
+    fn u16(&mut self) -> Result<u16, StateError> {
+        let mut buf = [0; 2];
+        buf.copy_from_slice(self.bytes(2)?);
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    fn u64(&mut self) -> Result<u64, StateError> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.bytes(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+}
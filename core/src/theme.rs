@@ -0,0 +1,88 @@
+use crate::image::PALETTE;
+
+/// Colours of pixels by XO-CHIP planes they are set in: none, plane 1, plane 2, both
+pub type Palette = [[u8; 3]; 4];
+
+/// Named palette, picked on the command line or in the config file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub name: String,
+    pub palette: Palette,
+}
+
+impl Theme {
+    /// Create theme from all four colours
+    pub fn new(name: &str, palette: Palette) -> Self {
+        Self {
+            name: name.to_string(),
+            palette,
+        }
+    }
+
+    /// Create theme from background and foreground colours.
+    ///
+    /// XO-CHIP plane 2 gets two thirds of the way from background to foreground, pixels
+    /// in both planes one third, same as the default black and white palette.
+    pub fn from_colours(name: &str, background: [u8; 3], foreground: [u8; 3]) -> Self {
+        Self::new(
+            name,
+            [
+                background,
+                foreground,
+                mix(background, foreground, 2),
+                mix(background, foreground, 1),
+            ],
+        )
+    }
+
+    /// Themes always available, the first one being the default
+    pub fn builtin() -> Vec<Theme> {
+        vec![
+            Theme::new("default", PALETTE),
+            Theme::from_colours("amber", [0x1A, 0x10, 0x00], [0xFF, 0xB0, 0x00]),
+            Theme::from_colours("phosphor", [0x08, 0x18, 0x08], [0x33, 0xFF, 0x66]),
+            // Original Game Boy screen
+            Theme::new(
+                "lcd",
+                [
+                    [0x9B, 0xBC, 0x0F],
+                    [0x0F, 0x38, 0x0F],
+                    [0x30, 0x62, 0x30],
+                    [0x8B, 0xAC, 0x0F],
+                ],
+            ),
+            // Octo's default background, fill, fill2 and blend colours
+            Theme::new(
+                "octo",
+                [
+                    [0x99, 0x66, 0x00],
+                    [0xFF, 0xCC, 0x00],
+                    [0xFF, 0x66, 0x00],
+                    [0x66, 0x22, 0x00],
+                ],
+            ),
+        ]
+    }
+}
+
+/// Colour `thirds` thirds of the way from `from` to `to`
+fn mix(from: [u8; 3], to: [u8; 3], thirds: u16) -> [u8; 3] {
+    let mut out = [0; 3];
+
+    for (idx, channel) in out.iter_mut().enumerate() {
+        *channel = ((from[idx] as u16 * (3 - thirds) + to[idx] as u16 * thirds) / 3) as u8;
+    }
+
+    out
+}
+
+/// Parses a colour written as `#RRGGBB` (`#` optional)
+pub fn parse_colour(text: &str) -> Option<[u8; 3]> {
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let channel = |idx: usize| u8::from_str_radix(&hex[idx * 2..idx * 2 + 2], 16).ok();
+    Some([channel(0)?, channel(1)?, channel(2)?])
+}
@@ -0,0 +1,82 @@
+use std::fmt;
+
+use crate::instruction::Instruction;
+
+/// One executed instruction, with the registers it left behind.
+///
+/// Displays as a single line meant for diffing against traces of other emulators:
+/// instruction count, PC, opcode, disassembly, I and SP, then the V registers that
+/// changed, e.g. `00000004 0208 A2EA ld I, #746                I=02EA SP=0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// Number of instructions executed before this one since tracing started
+    pub cycle: u64,
+    pub pc: u16,
+    pub opcode: u16,
+    /// V registers before executing the instruction
+    pub before: [u8; 0x10],
+    /// V registers after executing the instruction
+    pub v: [u8; 0x10],
+    /// I after executing the instruction
+    pub i: u16,
+    /// SP after executing the instruction
+    pub sp: u8,
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = Instruction::decode(self.opcode).to_string();
+
+        write!(
+            f,
+            "{:08} {:04X} {:04X} {:<24} I={:04X} SP={:X}",
+            self.cycle, self.pc, self.opcode, text, self.i, self.sp
+        )?;
+
+        for (x, (before, after)) in self.before.iter().zip(self.v.iter()).enumerate() {
+            if before != after {
+                write!(f, " V{:X}={:02X}", x, after)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Records executed instructions, see `Emulator::tracer`.
+///
+/// Entries are buffered until `drain`ed, e.g. after every `run_for`, to be written to a
+/// file or handed to a callback.
+#[derive(Debug, Clone, Default)]
+pub struct Tracer {
+    /// Inclusive address ranges to record instructions from, all of them if empty
+    pub ranges: Vec<(u16, u16)>,
+    /// Number of instructions executed since tracing started, including unrecorded ones
+    pub cycles: u64,
+    entries: Vec<TraceEntry>,
+}
+
+impl Tracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether instruction at given address is recorded
+    pub fn traces(&self, pc: u16) -> bool {
+        self.ranges.is_empty()
+            || self
+                .ranges
+                .iter()
+                .any(|&(start, end)| (start..=end).contains(&pc))
+    }
+
+    /// Records an executed instruction
+    pub fn record(&mut self, entry: TraceEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Takes all entries recorded so far, oldest first
+    pub fn drain(&mut self) -> impl Iterator<Item = TraceEntry> + '_ {
+        self.entries.drain(..)
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+
+use crate::instruction::Instruction;
+
+/// Kind of memory access a watchpoint stops on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Any read by an instruction, e.g. `Dxyn` or `Fx65`
+    Read,
+    /// Any write by an instruction, e.g. `Fx33` or `Fx55`
+    Write,
+    /// Write that changes the value stored
+    Change,
+}
+
+impl Access {
+    /// Names as typed in debugger commands
+    pub const NAMES: [&'static str; 3] = ["read", "write", "change"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "read" => Some(Access::Read),
+            "write" => Some(Access::Write),
+            "change" => Some(Access::Change),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Access::Read => "read",
+            Access::Write => "write",
+            Access::Change => "change",
+        }
+    }
+}
+
+/// Stops execution after an instruction accesses memory from `start` to `end` (inclusive).
+///
+/// Only data accessed by instructions is watched, fetching instructions themselves is not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub start: u16,
+    pub end: u16,
+    pub access: Access,
+}
+
+impl Watchpoint {
+    /// Whether reading (`new` is None) or writing byte at given address triggers this
+    pub fn matches(&self, addr: usize, old: u8, new: Option<u8>) -> bool {
+        let hit = match (self.access, new) {
+            (Access::Read, None) => true,
+            (Access::Write, Some(_)) => true,
+            (Access::Change, Some(new)) => new != old,
+            _ => false,
+        };
+
+        hit && (self.start as usize..=self.end as usize).contains(&addr)
+    }
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{} {:#06x}", self.access.name(), self.start)
+        } else {
+            write!(
+                f,
+                "{} {:#06x}-{:#06x}",
+                self.access.name(),
+                self.start,
+                self.end
+            )
+        }
+    }
+}
+
+/// First watched access made by the last instruction executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit {
+    pub watchpoint: Watchpoint,
+    /// Address accessed
+    pub addr: u16,
+    /// Value before the access
+    pub old: u8,
+    /// Value written, if it was a write
+    pub new: Option<u8>,
+    /// Address of the instruction that made the access
+    pub pc: u16,
+    /// Instruction that made the access, as it was before executing it
+    pub instruction: Instruction,
+}
+
+impl fmt::Display for WatchHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "watchpoint {}: {:#06x} ",
+            self.watchpoint.access.name(),
+            self.addr
+        )?;
+
+        match self.new {
+            Some(new) => write!(f, "{:#04x} -> {:#04x}", self.old, new)?,
+            None => write!(f, "is {:#04x}", self.old)?,
+        }
+
+        write!(f, ", by {:#06x}: {}", self.pc, self.instruction)
+    }
+}
@@ -0,0 +1,22 @@
+//! Assembler tests: listings assemble back into the ROMs they were made from.
+
+use chip8_core::{asm, disasm};
+
+const PONG: &[u8] = include_bytes!("../../compiler/roms/pong.ch8");
+const TICTACTOE: &[u8] = include_bytes!("../../compiler/roms/tictactoe.bin");
+
+#[test]
+fn pong_listing() {
+    // Made by `compiler/asm.ts`, with functions moved around and `drw` heights left out
+    let rom = asm::assemble(include_str!("../../compiler/roms/pong.s")).unwrap();
+    assert!(rom == PONG, "pong.s does not assemble into pong.ch8");
+}
+
+#[test]
+fn own_listings() {
+    for (name, rom) in [("pong.ch8", PONG), ("tictactoe.bin", TICTACTOE)] {
+        let listing = disasm::disassemble(rom);
+        let assembled = asm::assemble(&listing).unwrap_or_else(|err| panic!("{}: {}", name, err));
+        assert!(assembled == rom, "{} changed", name);
+    }
+}
@@ -0,0 +1,77 @@
+//! Sound tests, run without an audio device: the beep is recorded into an in-memory WAV file.
+
+use std::{io::Cursor, time::Duration};
+
+use chip8_core::{
+    audio::{AudioSink, Beeper, WavSink},
+    emu::Emulator,
+};
+
+const SAMPLE_RATE: u32 = 48_000;
+/// Samples generated per frame at `SAMPLE_RATE`
+const FRAME_SAMPLES: usize = SAMPLE_RATE as usize / 60;
+/// Audio played per frame, a hair longer than 1/60 s so no frame gets a sample less
+const FRAME: Duration = Duration::from_micros(16_667);
+
+/// Waits 5 frames on the delay timer, sets the sound timer to 10, then loops forever
+const ROM: [u16; 8] = [
+    0x6005, 0xF015, // LD DT, 5
+    0xF007, 0x3000, 0x1204, // wait until DT is 0
+    0x610A, 0xF118, // LD ST, 10
+    0x120E, // JP 0x20E
+];
+
+fn u32_at(data: &[u8], pos: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&data[pos..pos + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+/// Runs the ROM for given number of frames, playing the beep into the sink after every frame.
+///
+/// Returns the sound timer after every frame.
+fn run(frames: usize, sink: &mut dyn AudioSink) -> Vec<u8> {
+    let rom: Vec<u8> = ROM.iter().flat_map(|op| op.to_be_bytes()).collect();
+
+    let mut emu = Emulator::with_seed(0);
+    emu.load_rom(&rom).unwrap();
+    let mut beeper = Beeper::default();
+
+    (0..frames)
+        .map(|_| {
+            emu.cycle().unwrap();
+            beeper.play(&emu, FRAME, sink).unwrap();
+            emu.st
+        })
+        .collect()
+}
+
+#[test]
+fn beep_into_wav() {
+    let mut wav = Vec::new();
+    let mut sink = WavSink::new(Cursor::new(&mut wav), SAMPLE_RATE).unwrap();
+    let timers = run(30, &mut sink);
+    drop(sink);
+
+    // RIFF and data sizes match what follows them
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(u32_at(&wav, 4) as usize, wav.len() - 8);
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(u32_at(&wav, 24), SAMPLE_RATE);
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(u32_at(&wav, 40) as usize, wav.len() - 44);
+
+    let samples: Vec<i16> = wav[44..]
+        .chunks(2)
+        .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    assert_eq!(samples.len(), 30 * FRAME_SAMPLES);
+
+    // Sound only in the frames the sound timer is active
+    for (frame, (samples, st)) in samples.chunks(FRAME_SAMPLES).zip(&timers).enumerate() {
+        let loud = samples.iter().any(|sample| *sample != 0);
+        assert_eq!(loud, *st > 0, "frame {} with ST={}", frame, st);
+    }
+    let beeping = timers.iter().filter(|st| **st > 0).count();
+    assert_eq!((beeping, timers[0], timers[29]), (9, 0, 0), "{:?}", timers);
+}
@@ -0,0 +1,89 @@
+//! Timing tests: timers tick 60 times per emulated second and instructions run at `ips`,
+//! however finely the time is handed to `run_for`, including long stalls caught up at once.
+
+use std::time::Duration;
+
+use chip8_core::{emu::Emulator, rng::Rng, trace::Tracer};
+
+/// Loops forever on `JP 0x200`, counting instructions with a tracer
+fn emulator(ips: u32) -> Emulator {
+    let mut emu = Emulator::with_seed(0);
+    emu.load_rom(&[0x12, 0x00]).unwrap();
+    emu.ips = ips;
+    emu.dt = 255;
+    emu.tracer = Some(Tracer::new());
+    emu
+}
+
+/// Runs for `total` nanoseconds, handed over in slices of `slice` nanoseconds
+fn run_sliced(emu: &mut Emulator, total: u64, slice: u64) {
+    let mut left = total;
+    while left > 0 {
+        let step = slice.min(left);
+        emu.run_for(Duration::from_nanos(step)).unwrap();
+        emu.tracer.as_mut().unwrap().drain().for_each(drop);
+        left -= step;
+    }
+}
+
+/// Instructions executed so far
+fn instructions(emu: &Emulator) -> u64 {
+    emu.tracer.as_ref().unwrap().cycles
+}
+
+#[test]
+fn timers_at_60_hz() {
+    // One call, frames, milliseconds, uneven slices and slices shorter than an instruction
+    let slices = [1_000_000_000, 16_666_667, 1_000_000, 123_457, 10_000];
+
+    for &ips in &[60, 600, 1000, 7_777, 50_000] {
+        for &slice in &slices {
+            let mut emu = emulator(ips);
+            run_sliced(&mut emu, 1_000_000_000, slice);
+
+            let what = format!("{} ips in {} ns slices", ips, slice);
+            assert_eq!(emu.clock.timer_ticks, 60, "{}", what);
+            assert_eq!(emu.dt, 255 - 60, "{}", what);
+            assert_eq!(emu.clock.now, 1_000_000_000, "{}", what);
+            assert_eq!(instructions(&emu), ips as u64, "{}", what);
+        }
+    }
+}
+
+#[test]
+fn random_slices() {
+    let mut rng = Rng::new(4);
+    let mut emu = emulator(900);
+
+    let mut total = 0;
+    while total < 3_000_000_000 {
+        let slice = (1 + rng.next_u64() % 20_000_000).min(3_000_000_000 - total);
+        emu.run_for(Duration::from_nanos(slice)).unwrap();
+        emu.tracer.as_mut().unwrap().drain().for_each(drop);
+        total += slice;
+    }
+
+    assert_eq!(emu.clock.timer_ticks, 180);
+    assert_eq!(instructions(&emu), 2700);
+}
+
+#[test]
+fn catching_up_after_stall() {
+    // A frontend stalled for 5 seconds hands all of it over at once, and ends up exactly
+    // where steady frames would have
+    let frame = 16_666_667;
+    let mut steady = emulator(600);
+    run_sliced(&mut steady, 7_000_000_000, frame);
+
+    let mut stalled = emulator(600);
+    run_sliced(&mut stalled, 1_000_000_000, frame);
+    run_sliced(&mut stalled, 5_000_000_000, 5_000_000_000);
+    assert_eq!(stalled.clock.timer_ticks, 360);
+    assert_eq!(stalled.dt, 0);
+    run_sliced(&mut stalled, 1_000_000_000, frame);
+
+    assert_eq!(stalled.clock, steady.clock);
+    assert_eq!(stalled.clock.timer_ticks, 420);
+    assert_eq!(instructions(&stalled), 4200);
+    assert_eq!(instructions(&stalled), instructions(&steady));
+}
@@ -0,0 +1,258 @@
+//! Conformance runner tests, against a small ROM drawing ticks and crosses the way test
+//! suite ROMs do, and the suite shipped in `tests/conformance`.
+
+use std::{fs, path::Path};
+
+use chip8_core::{
+    asm,
+    conformance::{self, find_marks, Glyph, Mark, Suite, Verdict},
+    emu::Emulator,
+};
+
+/// Draws a tick at (8, 2), a cross at (20, 3), a tick at (2, 10) and a cross at (12, 10),
+/// then loops forever
+const ROM: [u16; 21] = [
+    0x6008, 0x6102, 0xA22A, 0xD015, // tick
+    0x6014, 0x6103, 0xA22F, 0xD015, // cross, one pixel lower on the same line
+    0x6002, 0x610A, 0xA22A, 0xD015, // tick on the next line
+    0x600C, 0xA22F, 0xD015, // cross
+    0x121E, // JP 0x21E
+    0x0000, 0x0000, 0x0000, 0x0000, 0x0000,
+];
+
+/// Tick and cross sprites, after the program at 0x22A
+const SPRITES: [u8; 10] = [
+    0b0000_0010,
+    0b0000_0100,
+    0b1000_1000,
+    0b0101_0000,
+    0b0010_0000,
+    0b1000_1000,
+    0b0101_0000,
+    0b0010_0000,
+    0b0101_0000,
+    0b1000_1000,
+];
+
+fn rom() -> Vec<u8> {
+    let mut rom: Vec<u8> = ROM.iter().flat_map(|op| op.to_be_bytes()).collect();
+    rom.truncate(0x2A);
+    rom.extend_from_slice(&SPRITES);
+    rom
+}
+
+fn suite(text: &str) -> Suite {
+    Suite::parse(text).unwrap_or_else(|err| panic!("{}", err))
+}
+
+#[test]
+fn marks_in_reading_order() {
+    let suite = suite("");
+    let mut emu = Emulator::with_seed(0);
+    emu.load_sprites();
+    emu.load_rom(&rom()).unwrap();
+    for _ in 0..16 {
+        emu.vblank_wait = false;
+        emu.step().unwrap();
+    }
+
+    let mark = |verdict, x, y| Mark { verdict, x, y };
+    assert_eq!(
+        find_marks(&emu, &suite.pass, &suite.fail),
+        vec![
+            mark(Verdict::Pass, 8, 2),
+            mark(Verdict::Fail, 20, 3),
+            mark(Verdict::Pass, 2, 10),
+            mark(Verdict::Fail, 12, 10),
+        ]
+    );
+}
+
+#[test]
+fn named_results() {
+    let suite = suite(
+        "[test opcodes]\n# Opcodes\nrom = test.ch8\nquirks = schip\nframes = 10\n\
+         results = 3XNN 4XNN 5XY0 7XNN\n",
+    );
+    let report = conformance::run(&suite, &suite.tests[0], &rom());
+
+    assert_eq!(report.error, None);
+    assert_eq!(
+        report.results,
+        vec![
+            (String::from("3XNN"), Verdict::Pass),
+            (String::from("4XNN"), Verdict::Fail),
+            (String::from("5XY0"), Verdict::Pass),
+            (String::from("7XNN"), Verdict::Fail),
+        ]
+    );
+    assert_eq!(report.counts(), (2, 2));
+    assert!(!report.passed());
+    assert!(report.to_string().contains("  FAIL  4XNN\n"));
+}
+
+#[test]
+fn missing_verdicts() {
+    let suite = suite("[test short]\nrom = test.ch8\nframes = 10\nresults = a b c\n");
+    let report = conformance::run(&suite, &suite.tests[0], &rom());
+
+    assert_eq!(
+        report.error.as_deref(),
+        Some("expected 3 verdicts, found 4")
+    );
+    assert!(!report.passed());
+
+    // Not drawn before the first frame ends
+    let suite = self::suite("[test early]\nrom = test.ch8\nframes = 0\n");
+    let report = conformance::run(&suite, &suite.tests[0], &rom());
+    assert_eq!(report.error.as_deref(), Some("no verdicts found"));
+}
+
+#[test]
+fn emulator_errors() {
+    let suite = suite("[test bad]\nrom = bad.ch8\n");
+    let report = conformance::run(&suite, &suite.tests[0], &[0xFF, 0xFF]);
+
+    assert!(report.error.unwrap().ends_with("at frame 0"));
+    assert!(report.results.is_empty());
+}
+
+#[test]
+fn pokes_and_glyphs() {
+    // Glyphs swapped, and the first tick drawn as a cross by pointing I at it
+    let suite = suite(
+        "[glyph pass]\n#...#\n.#.#.\n..#..\n.#.#.\n#...#\n\n\
+         [glyph fail]\n......#\n.....#.\n#...#..\n.#.#...\n..#....\n\n\
+         [test poked]\nrom = test.ch8\nframes = 10\npoke = 0x205 0x2F\n",
+    );
+    let report = conformance::run(&suite, &suite.tests[0], &rom());
+
+    assert_eq!(report.error, None);
+    assert_eq!(
+        report
+            .results
+            .iter()
+            .map(|(name, verdict)| (name.as_str(), *verdict))
+            .collect::<Vec<_>>(),
+        vec![
+            ("#1 at (8, 2)", Verdict::Pass),
+            ("#2 at (20, 3)", Verdict::Pass),
+            ("#3 at (2, 10)", Verdict::Fail),
+            ("#4 at (12, 10)", Verdict::Pass),
+        ]
+    );
+}
+
+#[test]
+fn parse_errors() {
+    let cases = [
+        (
+            "rom = test.ch8\n",
+            "expected a section like '[test NAME]' on line 1",
+        ),
+        (
+            "[test a]\nframes = 10\n",
+            "test 'a' needs a rom at end of file",
+        ),
+        (
+            "[test a]\nrom = a\n[test b\n",
+            "unterminated section '[test b' on line 3",
+        ),
+        ("[suite a]\n", "unknown section '[suite a]' on line 1"),
+        (
+            "[test a]\nrom = a\nspeed = 2\n",
+            "unknown setting 'speed' on line 3",
+        ),
+        (
+            "[test a]\nrom = a\nframes = -1\n",
+            "invalid frame count '-1' on line 3",
+        ),
+        (
+            "[test a]\nrom = a\npoke = 0x200 256\n",
+            "invalid poke '0x200 256' on line 3",
+        ),
+        (
+            "[test a]\nrom = a\ninput = 10 down G\n",
+            "invalid input '10 down G' on line 3",
+        ),
+        (
+            "[glyph pass]\n##\n#\n",
+            "glyph rows must be non-empty and equally wide at end of file",
+        ),
+        (
+            "[glyph fail]\n..\n[test a]\nrom = a\n",
+            "glyph has no lit pixels on line 3",
+        ),
+    ];
+
+    for (text, expected) in &cases {
+        assert_eq!(
+            Suite::parse(text).err().as_deref(),
+            Some(*expected),
+            "{:?}",
+            text
+        );
+    }
+
+    assert!(Glyph::parse(vec!["#x"]).is_err());
+}
+
+#[test]
+fn shipped_suite() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance");
+    let source = fs::read_to_string(dir.join("alu.s")).unwrap();
+    let rom = fs::read(dir.join("alu.ch8")).unwrap();
+    assert_eq!(
+        asm::assemble(&source),
+        Ok(rom.clone()),
+        "alu.ch8 is out of date"
+    );
+
+    let suite = suite(&fs::read_to_string(dir.join("suite.txt")).unwrap());
+    let reports: Vec<_> = suite
+        .tests
+        .iter()
+        .map(|test| conformance::run(&suite, test, &fs::read(dir.join(&test.rom)).unwrap()))
+        .collect();
+
+    // All pass on the VIP, only the shift using Vy fails without its quirk
+    let expected = |failing: &str| {
+        ["8XY4", "8XY5", "FX33", "FX55", "8XY6"]
+            .iter()
+            .map(|name| match *name == failing {
+                true => (name.to_string(), Verdict::Fail),
+                false => (name.to_string(), Verdict::Pass),
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(reports.len(), 2);
+    for (report, failing) in reports.iter().zip(["", "8XY6"]) {
+        assert_eq!(report.error, None, "{}", report);
+        assert_eq!(report.results, expected(failing), "{}", report);
+    }
+}
+
+#[test]
+fn timendus_suite() {
+    // The ROMs are not bundled, but every test names all the verdicts its ROM draws
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance/timendus/suite.txt");
+    let suite = suite(&fs::read_to_string(path).unwrap());
+
+    let counts: Vec<_> = suite
+        .tests
+        .iter()
+        .map(|test| (test.name.as_str(), test.results.len()))
+        .collect();
+    assert_eq!(
+        counts,
+        vec![
+            ("corax+", 18),
+            ("flags", 27),
+            ("quirks-chip8", 6),
+            ("quirks-schip", 6),
+            ("quirks-xochip", 6),
+            ("keypad-getkey", 1),
+        ]
+    );
+    assert_eq!(suite.tests[2].pokes, vec![(0x1FF, 1)]);
+}
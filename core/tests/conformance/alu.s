@@ -0,0 +1,91 @@
+; Small test ROM in the style of the community test suites: every check draws a tick when
+; it passes and a cross when it fails, left to right. Checks, in order:
+;   8XY4  add with carry
+;   8XY5  subtract with borrow
+;   FX33  BCD
+;   FX55  store and load registers
+;   8XY6  shift, taking Vy on the COSMAC VIP only, so it fails with other quirks
+main:
+  ld Vc, #4
+  ld Vd, #4
+
+  ld V0, #255
+  ld V1, #1
+  add V0, V1
+  ld Ve, #1
+  se V0, #0
+  ld Ve, #0
+  se Vf, #1
+  ld Ve, #0
+  call mark
+
+  ld V0, #16
+  ld V1, #32
+  sub V0, V1
+  ld Ve, #1
+  se V0, #240
+  ld Ve, #0
+  se Vf, #0
+  ld Ve, #0
+  call mark
+
+  ld V0, #123
+  ld I, scratch
+  ld I, V0.B
+  ld V2, I
+  ld Ve, #1
+  se V0, #1
+  ld Ve, #0
+  se V1, #2
+  ld Ve, #0
+  se V2, #3
+  ld Ve, #0
+  call mark
+
+  ld V0, #7
+  ld V1, #8
+  ld V2, #9
+  ld I, scratch
+  ld I, V2
+  ld V0, #0
+  ld V1, #0
+  ld V2, #0
+  ld I, scratch
+  ld V2, I
+  ld Ve, #1
+  se V0, #7
+  ld Ve, #0
+  se V1, #8
+  ld Ve, #0
+  se V2, #9
+  ld Ve, #0
+  call mark
+
+  ld V0, #0
+  ld V1, #4
+  shr V0, V1
+  ld Ve, #1
+  se V0, #2
+  ld Ve, #0
+  call mark
+
+done:
+  jmp done
+
+; Draws a tick at (Vc, Vd) if Ve is 1, a cross otherwise, then moves right
+mark:
+  ld I, cross
+  se Ve, #1
+  jmp draw
+  ld I, tick
+draw:
+  drw Vc, Vd, #5
+  add Vc, #8
+  ret
+
+tick:
+  db 0x02, 0x04, 0x88, 0x50, 0x20
+cross:
+  db 0x88, 0x50, 0x20, 0x50, 0x88
+scratch:
+  db 0, 0, 0
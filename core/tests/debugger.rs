@@ -0,0 +1,177 @@
+//! Debugger tests: breakpoint conditions, and stepping over, out of and on from breakpoints in
+//! a routine calling itself, where the same address comes up at several stack depths.
+
+use std::time::Duration;
+
+use chip8_core::{
+    debugger::{Compare, Condition, Debugger, Target},
+    emu::{Emulator, StepOutcome},
+};
+
+/// Calls a routine which calls itself until V2 is 3, counting returns in V3
+const ROM: [u16; 7] = [
+    0x2204, // 0x200: CALL 0x204
+    0x1202, // 0x202: JP 0x202
+    0x7201, // 0x204: ADD V2, 1
+    0x3203, // 0x206: SE V2, 3
+    0x2204, // 0x208: CALL 0x204
+    0x7301, // 0x20A: ADD V3, 1
+    0x00EE, // 0x20C: RET
+];
+
+fn emulator() -> Emulator {
+    let rom: Vec<u8> = ROM.iter().flat_map(|op| op.to_be_bytes()).collect();
+    let mut emu = Emulator::with_seed(0);
+    emu.load_rom(&rom).unwrap();
+    emu
+}
+
+/// Runs the command, which must succeed
+fn command(debugger: &mut Debugger, emu: &mut Emulator, line: &str) -> String {
+    debugger
+        .command(emu, line)
+        .unwrap_or_else(|err| panic!("{}: {}", line, err))
+}
+
+/// Runs until the debugger stops, failing if it does not within a second
+fn run_to_stop(debugger: &mut Debugger, emu: &mut Emulator) {
+    assert_eq!(
+        debugger.run_for(emu, Duration::from_secs(1)),
+        Ok(StepOutcome::Breakpoint)
+    );
+    assert!(debugger.stopped);
+}
+
+#[test]
+fn conditions() {
+    let cond = |target, compare, value| Condition {
+        target,
+        compare,
+        value,
+    };
+    let cases = [
+        ("V3 == 0x10", cond(Target::V(3), Compare::Eq, 0x10)),
+        ("va<=10", cond(Target::V(0xA), Compare::Le, 10)),
+        ("vA < 10", cond(Target::V(0xA), Compare::Lt, 10)),
+        ("I>=0b11", cond(Target::I, Compare::Ge, 3)),
+        ("dt > 0", cond(Target::Dt, Compare::Gt, 0)),
+        ("ST != 1", cond(Target::St, Compare::Ne, 1)),
+        ("pc == 0x200", cond(Target::Pc, Compare::Eq, 0x200)),
+        ("SP<2", cond(Target::Sp, Compare::Lt, 2)),
+    ];
+    for (text, expected) in &cases {
+        assert_eq!(Condition::parse(text), Ok(*expected), "{}", text);
+    }
+    assert_eq!(cases[1].1.to_string(), "VA <= 0xa");
+
+    // `<=` and `<` differ right at the value
+    let mut emu = emulator();
+    emu.v[0xA] = 10;
+    assert!(cases[1].1.holds(&emu));
+    assert!(!cases[2].1.holds(&emu));
+
+    for (text, err) in [
+        ("VG == 1", "unknown register 'VG'"),
+        ("V10 == 1", "unknown register 'V10'"),
+        ("X < 1", "unknown register 'X'"),
+        ("V1 = 1", "missing comparison in 'V1 = 1'"),
+        ("V1 <= x", "invalid number 'x'"),
+    ] {
+        assert_eq!(Condition::parse(text), Err(String::from(err)), "{}", text);
+    }
+}
+
+#[test]
+fn step_and_next() {
+    let mut emu = emulator();
+    let mut debugger = Debugger::new();
+    debugger.stop();
+
+    // Into the routine up to its CALL to itself
+    assert_eq!(
+        command(&mut debugger, &mut emu, "s 3"),
+        "stopped at 0x0208: call #0x0204"
+    );
+    assert_eq!((emu.sp, emu.v[2]), (1, 1));
+
+    // The return address comes up twice deeper in the stack before the call returns
+    command(&mut debugger, &mut emu, "next");
+    assert!(!debugger.stopped);
+    run_to_stop(&mut debugger, &mut emu);
+    assert_eq!((emu.pc, emu.sp, emu.v[2], emu.v[3]), (0x20A, 1, 3, 2));
+
+    // Anything but a CALL is a plain step
+    command(&mut debugger, &mut emu, "n");
+    assert!(debugger.stopped);
+    assert_eq!((emu.pc, emu.v[3]), (0x20C, 3));
+}
+
+#[test]
+fn step_out() {
+    let mut emu = emulator();
+    let mut debugger = Debugger::new();
+    debugger.stop();
+
+    // In the innermost call, right after the check that ends the recursion
+    command(&mut debugger, &mut emu, "s 9");
+    assert_eq!((emu.pc, emu.sp, emu.v[3]), (0x20A, 3, 0));
+
+    // Stops after RET, back in the caller right after its CALL
+    command(&mut debugger, &mut emu, "o");
+    run_to_stop(&mut debugger, &mut emu);
+    assert_eq!((emu.pc, emu.sp, emu.v[3]), (0x20A, 2, 1));
+
+    command(&mut debugger, &mut emu, "out");
+    run_to_stop(&mut debugger, &mut emu);
+    command(&mut debugger, &mut emu, "out");
+    run_to_stop(&mut debugger, &mut emu);
+    assert_eq!((emu.pc, emu.sp), (0x202, 0));
+
+    assert_eq!(
+        debugger.command(&mut emu, "out"),
+        Err(String::from("not inside a routine, stack is empty"))
+    );
+}
+
+#[test]
+fn continue_from_breakpoint() {
+    let mut emu = emulator();
+    let mut debugger = Debugger::new();
+
+    assert_eq!(
+        command(&mut debugger, &mut emu, "b 0x204"),
+        "breakpoint 0: 0x0204"
+    );
+    assert_eq!(
+        command(&mut debugger, &mut emu, "break if V3 == 2"),
+        "breakpoint 1: if V3 == 0x2"
+    );
+
+    // Continuing does not stop on the breakpoint just hit, but the next time it is reached
+    for sp in 1..=3 {
+        run_to_stop(&mut debugger, &mut emu);
+        assert_eq!((emu.pc, emu.sp), (0x204, sp));
+        command(&mut debugger, &mut emu, "c");
+    }
+
+    // Stops where V3 becomes 2, and would again after the next RET unless deleted
+    run_to_stop(&mut debugger, &mut emu);
+    assert_eq!((emu.pc, emu.sp, emu.v[3]), (0x20C, 2, 2));
+    assert_eq!(
+        command(&mut debugger, &mut emu, "delete 1"),
+        "deleted breakpoint 1: if V3 == 0x2"
+    );
+    assert_eq!(
+        debugger.command(&mut emu, "d 1"),
+        Err(String::from("no breakpoint 1"))
+    );
+    command(&mut debugger, &mut emu, "continue");
+
+    // Nothing left to stop on
+    assert_ne!(
+        debugger.run_for(&mut emu, Duration::from_secs(1)),
+        Ok(StepOutcome::Breakpoint)
+    );
+    assert!(!debugger.stopped);
+    assert_eq!((emu.pc, emu.sp, emu.v[3]), (0x202, 0, 3));
+}
@@ -0,0 +1,54 @@
+//! Decode cache tests: self-modifying code runs the same with the cache on and off, so an
+//! instruction decoded before being overwritten shows up as a difference.
+
+use std::time::Duration;
+
+use chip8_core::emu::Emulator;
+
+/// Rewrites an instruction it already ran on every pass of a loop, then patches the low byte
+/// of a later instruction and the one after it
+const ROM: [u16; 16] = [
+    0x6075, // 0x200: LD V0, 0x75
+    0x6100, // 0x202: LD V1, 0
+    0x7101, // 0x204: ADD V1, 1
+    0xA20C, // 0x206: LD I, 0x20C
+    0xF155, // 0x208: LD [I], V0-V1, making 0x20C `ADD V5, <V1>`
+    0x8220, // 0x20A: LD V2, V2
+    0x0000, // 0x20C: overwritten before it first runs
+    0x310A, // 0x20E: SE V1, 10
+    0x1204, // 0x210: JP 0x204
+    0x67C8, // 0x212: LD V7, 200
+    0xA21B, // 0x214: LD I, 0x21B
+    0xF733, // 0x216: LD B, V7, writing 2, 0, 0 from the middle of 0x21A
+    0x8990, // 0x218: LD V9, V9
+    0x6AFF, // 0x21A: LD VA, 0xFF, becoming LD VA, 2
+    0xFFFF, // 0x21C: invalid until it becomes 0000
+    0x121E, // 0x21E: JP 0x21E
+];
+
+fn run(decode_cache: bool) -> Emulator {
+    let rom: Vec<u8> = ROM.iter().flat_map(|op| op.to_be_bytes()).collect();
+    let mut emu = Emulator::with_seed(0);
+    emu.set_decode_cache(decode_cache);
+    emu.load_rom(&rom).unwrap();
+    assert_eq!(emu.decode_cache_enabled(), decode_cache);
+
+    emu.run_for(Duration::from_millis(500)).unwrap();
+    emu
+}
+
+#[test]
+fn self_modifying_code() {
+    let cached = run(true);
+    let uncached = run(false);
+
+    // ADD V5, 1 up to ADD V5, 10
+    assert_eq!(cached.v[5], 55);
+    assert_eq!(cached.v[0xA], 2);
+    assert_eq!(cached.pc, 0x21E);
+
+    assert_eq!(cached.v, uncached.v);
+    assert_eq!(cached.pc, uncached.pc);
+    assert_eq!(cached.memory, uncached.memory);
+    assert!(cached.save_state() == uncached.save_state());
+}
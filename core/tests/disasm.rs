@@ -0,0 +1,58 @@
+//! Disassembler tests against `compiler/roms/pong.s`, the listing made by `compiler/asm.ts`.
+
+use std::collections::BTreeMap;
+
+use chip8_core::{disasm, emu::ROM_OFFSET};
+
+const PONG: &[u8] = include_bytes!("../../compiler/roms/pong.ch8");
+const PONG_S: &str = include_str!("../../compiler/roms/pong.s");
+
+/// Instructions of a listing by the address they are annotated with, with `label_XXXX`
+/// jump targets turned into the `#0xADDR` the TypeScript tool writes instead
+fn instructions(listing: &str) -> BTreeMap<u16, String> {
+    let mut out = BTreeMap::new();
+
+    for line in listing.lines() {
+        let (text, annotation) = match line.split_once(';') {
+            Some(parts) => parts,
+            None => continue,
+        };
+        let addr = annotation
+            .split_once("@ 0x")
+            .and_then(|(_, addr)| u16::from_str_radix(addr.trim(), 16).ok())
+            .unwrap_or_else(|| panic!("bad annotation in '{}'", line));
+
+        let mut text = text.trim().to_string();
+        if let Some(idx) = text.find("label_") {
+            let offset = usize::from_str_radix(&text[idx + 6..], 16).unwrap();
+            text = format!("{}#0x{:04x}", &text[..idx], ROM_OFFSET + offset);
+        }
+
+        assert!(out.insert(addr, text).is_none(), "0x{:04x} twice", addr);
+    }
+
+    out
+}
+
+#[test]
+fn pong_matches_listing() {
+    let expected = instructions(PONG_S);
+    let actual = instructions(&disasm::disassemble(PONG));
+
+    assert_eq!(expected.len(), PONG.len() / 2);
+    for (addr, text) in &expected {
+        assert_eq!(actual.get(addr), Some(text), "at 0x{:04x}", addr);
+    }
+    assert_eq!(actual.len(), expected.len());
+}
+
+#[test]
+fn function_labels() {
+    let listing = disasm::disassemble(PONG);
+
+    // Called function and the entry point are labelled like in pong.s
+    for label in ["main:", "fn_00d4:"] {
+        assert!(listing.lines().any(|line| line == label), "{}", label);
+    }
+    assert!(listing.contains("call fn_00d4"));
+}
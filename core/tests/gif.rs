@@ -0,0 +1,241 @@
+//! GIF recorder tests: recordings are decoded again, with a plain LZW decoder written the way
+//! GIF viewers read codes, and must give back every frame with its delay.
+
+use std::time::Duration;
+
+use chip8_core::{emu::Emulator, gif::GifRecorder, image, rng::Rng};
+
+/// Decoded GIF file
+struct Gif {
+    width: usize,
+    height: usize,
+    palette: Vec<[u8; 3]>,
+    /// Delay in hundredths of a second and colour indices of every frame
+    frames: Vec<(u16, Vec<u8>)>,
+    /// Widest LZW code read, in bits
+    widest: u8,
+    /// Clear codes read after the first one of each frame, when the code table was full
+    resets: usize,
+}
+
+/// Reads codes of varying width, least significant bit first
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, width: u8) -> u16 {
+        let mut code = 0;
+        for bit in 0..width {
+            let byte = self.data[self.pos / 8];
+            code |= ((byte >> (self.pos % 8)) as u16 & 1) << bit;
+            self.pos += 1;
+        }
+        code
+    }
+}
+
+/// Decompresses image data, counting code widths and table resets into `gif`
+fn lzw_decode(gif: &mut Gif, min_code_size: u8, data: &[u8]) -> Vec<u8> {
+    let clear = 1usize << min_code_size;
+    let end = clear + 1;
+
+    let mut bits = BitReader { data, pos: 0 };
+    let mut table: Vec<Vec<u8>> = (0..clear).map(|idx| vec![idx as u8]).collect();
+    table.push(Vec::new());
+    table.push(Vec::new());
+    let mut width = min_code_size + 1;
+    let mut prev: Option<Vec<u8>> = None;
+    let mut out = Vec::new();
+
+    assert_eq!(bits.read(width) as usize, clear, "no clear code first");
+    loop {
+        let code = bits.read(width) as usize;
+        gif.widest = gif.widest.max(width);
+
+        if code == clear {
+            table.truncate(end + 1);
+            width = min_code_size + 1;
+            prev = None;
+            gif.resets += 1;
+            continue;
+        }
+        if code == end {
+            break;
+        }
+
+        let entry = match &prev {
+            _ if code < table.len() => table[code].clone(),
+            Some(prev) if code == table.len() => {
+                let mut entry = prev.clone();
+                entry.push(prev[0]);
+                entry
+            }
+            _ => panic!("code {} not in table of {}", code, table.len()),
+        };
+        if let Some(prev) = prev {
+            if table.len() < 0x1000 {
+                let mut added = prev;
+                added.push(entry[0]);
+                table.push(added);
+            }
+        }
+        if table.len() == 1 << width && width < 12 {
+            width += 1;
+        }
+
+        out.extend_from_slice(&entry);
+        prev = Some(entry);
+    }
+
+    // Nothing left but padding
+    assert_eq!(bits.pos.div_ceil(8), data.len(), "data after end code");
+    out
+}
+
+fn u16_at(data: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([data[pos], data[pos + 1]])
+}
+
+/// Joins data sub-blocks starting at `pos`, returns them and the position after the last
+fn sub_blocks(data: &[u8], mut pos: usize) -> (Vec<u8>, usize) {
+    let mut out = Vec::new();
+    while data[pos] != 0 {
+        let len = data[pos] as usize;
+        out.extend_from_slice(&data[pos + 1..pos + 1 + len]);
+        pos += 1 + len;
+    }
+    (out, pos + 1)
+}
+
+/// Decodes GIF written by `GifRecorder`, checking the parts it always writes
+fn decode(data: &[u8]) -> Gif {
+    assert_eq!(&data[..6], b"GIF89a");
+    let mut gif = Gif {
+        width: u16_at(data, 6) as usize,
+        height: u16_at(data, 8) as usize,
+        palette: Vec::new(),
+        frames: Vec::new(),
+        widest: 0,
+        resets: 0,
+    };
+
+    // Global colour table of 4 entries
+    assert_eq!(data[10] & 0x87, 0x81);
+    gif.palette = data[13..25]
+        .chunks(3)
+        .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+        .collect();
+
+    let mut pos = 25;
+    let mut delay = None;
+    loop {
+        match data[pos] {
+            0x21 if data[pos + 1] == 0xFF => {
+                // Application extension: NETSCAPE2.0 looping forever
+                assert_eq!(&data[pos + 2..pos + 14], b"\x0BNETSCAPE2.0");
+                let (looping, next) = sub_blocks(data, pos + 14);
+                assert_eq!(looping, [1, 0, 0]);
+                pos = next;
+            }
+            0x21 if data[pos + 1] == 0xF9 => {
+                // Graphic control extension
+                assert_eq!(data[pos + 2], 4);
+                delay = Some(u16_at(data, pos + 4));
+                pos += 8;
+            }
+            0x2C => {
+                // Image descriptor covering the whole image, without local colour table
+                assert_eq!(u16_at(data, pos + 1), 0);
+                assert_eq!(u16_at(data, pos + 3), 0);
+                assert_eq!(u16_at(data, pos + 5) as usize, gif.width);
+                assert_eq!(u16_at(data, pos + 7) as usize, gif.height);
+                assert_eq!(data[pos + 9], 0);
+
+                let min_code_size = data[pos + 10];
+                let (compressed, next) = sub_blocks(data, pos + 11);
+                let pixels = lzw_decode(&mut gif, min_code_size, &compressed);
+                assert_eq!(pixels.len(), gif.width * gif.height);
+
+                gif.frames
+                    .push((delay.take().expect("frame without delay"), pixels));
+                pos = next;
+            }
+            0x3B => break,
+            other => panic!("unexpected block {:#x} at {}", other, pos),
+        }
+    }
+    assert_eq!(pos + 1, data.len());
+
+    gif
+}
+
+#[test]
+fn small_recording() {
+    let mut emu = Emulator::with_seed(0);
+    let mut recorder = GifRecorder::new(&image::PALETTE, 2);
+    let mut displays = Vec::new();
+
+    // Shown for 1/10 s, 1/4 s, then 1/3 s in two steps as the display stays the same
+    for (frame, duration) in [(0, 100), (1, 250), (2, 200), (2, 133)] {
+        for (idx, px) in emu.display.iter_mut().enumerate() {
+            *px = ((idx + frame) % 3 == 0) as u8 * (frame as u8 + 1);
+        }
+        if displays.last() != Some(&emu.display) {
+            displays.push(emu.display.clone());
+        }
+        recorder.push(&emu, Duration::from_millis(duration));
+    }
+    assert_eq!(recorder.len(), 3);
+
+    let gif = decode(&recorder.finish());
+    assert_eq!((gif.width, gif.height), (128, 64));
+    assert_eq!(gif.palette, image::PALETTE.to_vec());
+
+    let delays: Vec<u16> = gif.frames.iter().map(|(delay, _)| *delay).collect();
+    assert_eq!(delays, vec![10, 25, 33]);
+    for ((_, pixels), display) in gif.frames.iter().zip(&displays) {
+        assert_eq!(*pixels, image::resize(display, 64, 32, 128, 64));
+    }
+    assert_eq!(gif.resets, 0);
+}
+
+#[test]
+fn long_recording() {
+    // Noise on a scaled up high resolution display fills the code table several times
+    let mut emu = Emulator::with_seed(0);
+    emu.set_hires(true);
+    let mut rng = Rng::new(9);
+    let mut recorder = GifRecorder::new(&image::PALETTE, 3);
+    let mut displays = Vec::new();
+
+    for _ in 0..3 {
+        for px in emu.display.iter_mut() {
+            *px = rng.next_u8() % 4;
+        }
+        displays.push(emu.display.clone());
+        recorder.push(&emu, Duration::from_millis(50));
+    }
+
+    let gif = decode(&recorder.finish());
+    assert_eq!((gif.width, gif.height), (128 * 3, 64 * 3));
+    assert_eq!(gif.frames.len(), 3);
+    for ((delay, pixels), display) in gif.frames.iter().zip(&displays) {
+        assert_eq!(*delay, 5);
+        assert_eq!(*pixels, image::resize(display, 128, 64, 128 * 3, 64 * 3));
+    }
+
+    assert_eq!(gif.widest, 12);
+    assert!(gif.resets >= 3, "{} table resets", gif.resets);
+}
+
+#[test]
+fn empty_recording() {
+    let recorder = GifRecorder::new(&image::PALETTE, 1);
+    assert!(recorder.is_empty());
+
+    let gif = decode(&recorder.finish());
+    assert_eq!((gif.width, gif.height), (0, 0));
+    assert!(gif.frames.is_empty());
+}
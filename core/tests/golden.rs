@@ -0,0 +1,173 @@
+//! Golden snapshot tests: bundled ROMs are run for a fixed number of frames with a fixed seed
+//! and scripted input, and the final display and registers are compared against snapshots
+//! stored in `tests/golden`.
+//!
+//! `compiler/roms/TICTAC.bin` has no case: the file is an HTML "403 Forbidden" page saved in
+//! place of the ROM, not a CHIP-8 program. Snapshotting it would only record what its text
+//! does when run as opcodes. Add its case once the real ROM is in the tree.
+//!
+//! After an intended change in behaviour, rerun with `UPDATE_GOLDEN=1` to rewrite the
+//! snapshots, then review the diff.
+
+use std::{env, fs, path::PathBuf};
+
+use chip8_core::{
+    emu::{Emulator, StepOutcome},
+    headless::{self, InputScript},
+    image,
+};
+
+/// Seed of the random number generator for every run
+const SEED: u64 = 5;
+
+/// ROM run for a number of frames, pressing keys as scripted
+struct Case {
+    /// Name of the snapshot in `tests/golden`
+    name: &'static str,
+    /// File in `compiler/roms`
+    rom: &'static str,
+    frames: u64,
+    /// Input script, see `InputScript`
+    input: &'static str,
+}
+
+/// Runs the case and returns its snapshot: hash, registers, display and the error if any
+fn snapshot(case: &Case) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../compiler/roms")
+        .join(case.rom);
+    let rom = fs::read(&path).unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+
+    let mut emu = Emulator::with_seed(SEED);
+    emu.load_sprites();
+    emu.load_rom(&rom).unwrap();
+    let mut script = InputScript::parse(case.input).unwrap();
+
+    let mut error = None;
+    while emu.clock.timer_ticks < case.frames {
+        script.apply(&mut emu);
+
+        match emu.cycle() {
+            Ok(StepOutcome::Exited) => break,
+            Ok(_) => {}
+            Err(err) => {
+                error = Some(err);
+                break;
+            }
+        }
+    }
+
+    let state = format!("{}{}", headless::registers(&emu), image::ascii(&emu));
+    let mut out = format!(
+        "# {}, {} frames, seed {}\nhash {:016x}\n{}",
+        case.rom,
+        case.frames,
+        SEED,
+        hash(state.as_bytes()),
+        state
+    );
+    if let Some(err) = error {
+        out.push_str(&format!(
+            "error: {} at frame {}\n",
+            err, emu.clock.timer_ticks
+        ));
+    }
+
+    out
+}
+
+/// 64-bit FNV-1a
+fn hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Lines that differ, expected above actual, with `^` under the columns that changed
+fn diff(expected: &str, actual: &str) -> String {
+    let mut out = String::new();
+    let (expected, actual): (Vec<&str>, Vec<&str>) =
+        (expected.lines().collect(), actual.lines().collect());
+
+    for idx in 0..expected.len().max(actual.len()) {
+        let (old, new) = (
+            expected.get(idx).copied().unwrap_or(""),
+            actual.get(idx).copied().unwrap_or(""),
+        );
+        if old == new {
+            continue;
+        }
+
+        let markers: String = (0..old.len().max(new.len()))
+            .map(|col| {
+                if old.as_bytes().get(col) == new.as_bytes().get(col) {
+                    ' '
+                } else {
+                    '^'
+                }
+            })
+            .collect();
+        out.push_str(&format!(
+            "line {}:\n  - {}\n  + {}\n    {}\n",
+            idx + 1,
+            old,
+            new,
+            markers.trim_end()
+        ));
+    }
+
+    out
+}
+
+/// Compares the case against its snapshot, or writes the snapshot with `UPDATE_GOLDEN` set
+fn check(case: &Case) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.txt", case.name));
+    let actual = snapshot(case);
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "could not read '{}': {}, run with UPDATE_GOLDEN=1 to create it",
+            path.display(),
+            err
+        )
+    });
+
+    if expected != actual {
+        panic!(
+            "{} does not match '{}':\n{}\nrun with UPDATE_GOLDEN=1 if this is intended",
+            case.rom,
+            path.display(),
+            diff(&expected, &actual)
+        );
+    }
+}
+
+#[test]
+fn pong() {
+    check(&Case {
+        name: "pong",
+        rom: "pong.ch8",
+        frames: 600,
+        // Left paddle up, then down, right paddle down meanwhile
+        input: "60 down 1\n90 up 1\n120 down 4\n200 up 4\n100 down D\n150 up D\n",
+    });
+}
+
+#[test]
+fn tictactoe() {
+    check(&Case {
+        name: "tictactoe",
+        rom: "tictactoe.bin",
+        frames: 600,
+        // Take the centre, then corners
+        input:
+            "60 down 5\n64 up 5\n180 down 1\n184 up 1\n300 down 9\n304 up 9\n420 down 3\n424 up 3\n",
+    });
+}
@@ -0,0 +1,129 @@
+//! PNG encoder tests: checksums against published values, and images taken apart chunk by
+//! chunk to check every header field and CRC, and that the pixels come back out.
+
+use chip8_core::{
+    emu::Emulator,
+    image::{self, adler32, crc32, PALETTE},
+};
+
+#[test]
+fn checksums() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+
+    assert_eq!(adler32(b""), 1);
+    assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    assert_eq!(adler32(&[0xFF; 6000]), 0xA497_59EA);
+}
+
+fn u32_at(data: &[u8], pos: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&data[pos..pos + 4]);
+    u32::from_be_bytes(bytes)
+}
+
+/// Splits PNG file into chunk types and data, checking the signature and every CRC
+fn chunks(png: &[u8]) -> Vec<([u8; 4], &[u8])> {
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+    let mut chunks = Vec::new();
+    let mut pos = 8;
+    while pos < png.len() {
+        let len = u32_at(png, pos) as usize;
+        let body = &png[pos + 4..pos + 8 + len];
+        assert_eq!(u32_at(png, pos + 8 + len), crc32(body), "chunk at {}", pos);
+
+        let mut kind = [0; 4];
+        kind.copy_from_slice(&body[..4]);
+        chunks.push((kind, &body[4..]));
+        pos += 12 + len;
+    }
+    assert_eq!(pos, png.len());
+
+    chunks
+}
+
+/// Takes zlib stream of stored deflate blocks apart, checking its header and checksum
+fn inflate_stored(zlib: &[u8]) -> Vec<u8> {
+    assert_eq!(&zlib[..2], &[0x78, 0x01]);
+    assert_eq!(u16::from_be_bytes([zlib[0], zlib[1]]) % 31, 0);
+
+    let mut raw = Vec::new();
+    let mut pos = 2;
+    loop {
+        let last = zlib[pos];
+        let len = u16::from_le_bytes([zlib[pos + 1], zlib[pos + 2]]);
+        let nlen = u16::from_le_bytes([zlib[pos + 3], zlib[pos + 4]]);
+        assert_eq!(len, !nlen);
+        raw.extend_from_slice(&zlib[pos + 5..pos + 5 + len as usize]);
+        pos += 5 + len as usize;
+
+        match last {
+            0 => {}
+            1 => break,
+            _ => panic!("bad block header {:#x}", last),
+        }
+    }
+
+    assert_eq!(u32_at(zlib, pos), adler32(&raw));
+    assert_eq!(pos + 4, zlib.len());
+    raw
+}
+
+/// Decodes PNG written by `encode_png` into its size and RGB pixels
+fn decode(png: &[u8]) -> (u32, u32, Vec<u8>) {
+    let chunks = chunks(png);
+    let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(kind, _)| kind).collect();
+    assert_eq!(kinds, vec![b"IHDR", b"IDAT", b"IEND"]);
+
+    // 8 bits per channel, RGB, deflate, no filtering, no interlacing
+    let header = chunks[0].1;
+    assert_eq!(header.len(), 13);
+    assert_eq!(&header[8..], &[8, 2, 0, 0, 0]);
+    let (width, height) = (u32_at(header, 0), u32_at(header, 4));
+    assert!(chunks[2].1.is_empty());
+
+    // Every scanline starts with filter type 0
+    let raw = inflate_stored(chunks[1].1);
+    let line = width as usize * 3 + 1;
+    assert_eq!(raw.len(), line * height as usize);
+    let mut rgb = Vec::new();
+    for scanline in raw.chunks(line) {
+        assert_eq!(scanline[0], 0);
+        rgb.extend_from_slice(&scanline[1..]);
+    }
+
+    (width, height, rgb)
+}
+
+#[test]
+fn small_image() {
+    let rgb: Vec<u8> = (0..2 * 3 * 3).collect();
+    assert_eq!(decode(&image::encode_png(2, 3, &rgb)), (2, 3, rgb));
+
+    // IHDR of a 1x1 image, byte for byte
+    let png = image::encode_png(1, 1, &[1, 2, 3]);
+    assert_eq!(
+        &png[8..33],
+        b"\x00\x00\x00\x0DIHDR\x00\x00\x00\x01\x00\x00\x00\x01\x08\x02\x00\x00\x00\x90\x77\x53\xDE"
+    );
+}
+
+#[test]
+fn display_over_several_blocks() {
+    // Scaled up high resolution display needs more than one stored block
+    let mut emu = Emulator::with_seed(0);
+    emu.set_hires(true);
+    for (idx, px) in emu.display.iter_mut().enumerate() {
+        *px = (idx % 7 % 4) as u8;
+    }
+
+    let png = image::png(&emu, &PALETTE, 3);
+    let (width, height, rgb) = decode(&png);
+    assert_eq!((width, height), (128 * 3, 64 * 3));
+    assert!(rgb.len() > 0xFFFF);
+
+    let pixels = image::resize(&emu.display, 128, 64, 128 * 3, 64 * 3);
+    assert_eq!(rgb, image::colour(&pixels, &PALETTE));
+}
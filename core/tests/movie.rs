@@ -0,0 +1,98 @@
+//! Movie tests: input recorded while playing replays into exactly the same emulator state,
+//! after going through the text format.
+
+use std::time::Duration;
+
+use chip8_core::{
+    emu::Emulator,
+    movie::{Movie, Playback},
+    quirks::Quirks,
+    rng::Rng,
+};
+
+const TICTACTOE: &[u8] = include_bytes!("../../compiler/roms/tictactoe.bin");
+
+/// Emulator with the ROM loaded, set up like the movie says
+fn emulator(seed: u64, ips: u32, quirks: Quirks) -> Emulator {
+    let mut emu = Emulator::with_seed(seed);
+    emu.ips = ips;
+    emu.quirks = quirks;
+    emu.load_sprites();
+    emu.load_rom(TICTACTOE).unwrap();
+    emu
+}
+
+/// Random run length, from well within a frame to a few frames
+fn chunk(rng: &mut Rng) -> Duration {
+    Duration::from_micros(100 + rng.next_u64() % 40_000)
+}
+
+#[test]
+fn replays_identically() {
+    for trial in 0..5 {
+        let mut rng = Rng::new(trial);
+
+        // Keys pressed and released at arbitrary times, usually in the middle of a frame
+        let mut emu = emulator(trial, 900, Quirks::cosmac_vip());
+        emu.movie = Some(Movie::new(&emu));
+        for _ in 0..300 {
+            emu.run_for(chunk(&mut rng)).unwrap();
+
+            let key = rng.next_u8() % 0x10;
+            match rng.next_u8() % 4 {
+                0 => emu.key_down(key),
+                1 => emu.key_up(key),
+                _ => {}
+            }
+        }
+        let recorded = emu.movie.take().unwrap();
+        assert!(
+            recorded.events.len() > 20,
+            "{} events",
+            recorded.events.len()
+        );
+
+        let movie = Movie::parse(&recorded.to_text()).unwrap();
+        assert_eq!(movie, recorded);
+
+        // Replayed in differently sized steps
+        let mut replay = emulator(movie.seed, movie.ips, movie.quirks);
+        movie.check_rom(&replay).unwrap();
+        let mut playback = Playback::new(&movie);
+        while replay.clock.now < emu.clock.now {
+            let left = Duration::from_nanos(emu.clock.now - replay.clock.now);
+            playback
+                .run_for(&mut replay, chunk(&mut rng).min(left), |emu, duration| {
+                    emu.run_for(duration)
+                })
+                .unwrap();
+        }
+        // Events due right at the end are applied before running on
+        playback.apply(&mut replay);
+
+        assert!(playback.is_finished(), "trial {}", trial);
+        assert_eq!(replay.display, emu.display, "trial {}", trial);
+        assert_eq!((replay.v, replay.i, replay.pc), (emu.v, emu.i, emu.pc));
+        assert!(replay.save_state() == emu.save_state(), "trial {}", trial);
+    }
+}
+
+#[test]
+fn rejects_bad_movies() {
+    let emu = emulator(1, 600, Quirks::default());
+    let text = Movie::new(&emu).to_text();
+
+    let mut other = Emulator::with_seed(1);
+    other.load_rom(&TICTACTOE[1..]).unwrap();
+    assert!(Movie::parse(&text).unwrap().check_rom(&other).is_err());
+
+    for bad in [
+        text.replace("chip8 movie 1", "chip8 movie 2"),
+        text.replace("quirks", "quirks fast_mode"),
+        text.replace("ips 600", "ips 0"),
+        format!("{}10 0 down 1\n9 0 up 1\n", text),
+        format!("{}10 0 press 1\n", text),
+    ] {
+        assert!(Movie::parse(&bad).is_err(), "{}", bad);
+    }
+}
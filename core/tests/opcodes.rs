@@ -0,0 +1,547 @@
+//! Instruction tests: every case runs a few opcodes from `ROM_OFFSET` and checks the exact
+//! registers, memory and display they leave behind, under the quirks profiles that matter.
+
+use chip8_core::{
+    emu::{Emulator, StepOutcome, COLS, ROM_OFFSET},
+    error::EmulatorError,
+    quirks::Quirks,
+};
+
+/// Emulator with given quirks and registers, and `program` loaded as the ROM
+fn setup(quirks: Quirks, regs: &[(usize, u8)], program: &[u16]) -> Emulator {
+    let mut emu = Emulator::with_seed(0);
+    emu.quirks = quirks;
+    emu.load_sprites();
+
+    let rom: Vec<u8> = program.iter().flat_map(|op| op.to_be_bytes()).collect();
+    emu.load_rom(&rom).unwrap();
+
+    for &(reg, value) in regs {
+        emu.v[reg] = value;
+    }
+
+    emu
+}
+
+/// Executes given number of instructions, ignoring waits for the vertical blank
+fn steps(emu: &mut Emulator, count: usize) {
+    for _ in 0..count {
+        emu.vblank_wait = false;
+        assert_eq!(emu.step(), Ok(StepOutcome::Executed), "at {:#06x}", emu.pc);
+    }
+}
+
+/// Runs the whole program with given quirks and registers
+fn run(quirks: Quirks, regs: &[(usize, u8)], program: &[u16]) -> Emulator {
+    let mut emu = setup(quirks, regs, program);
+    steps(&mut emu, program.len());
+    emu
+}
+
+/// `(opcode, Vx, Vy, Vx after, VF after)` with x = 1 and y = 2
+type AluCase = (u16, u8, u8, u8, u8);
+
+/// Checks arithmetic cases, VF starting out as something no case sets it to
+fn check_alu(quirks: Quirks, cases: &[AluCase]) {
+    for &(op, vx, vy, result, flag) in cases {
+        let emu = run(quirks, &[(1, vx), (2, vy), (0xF, 0xAA)], &[op]);
+
+        assert_eq!(
+            (emu.v[1], emu.v[0xF]),
+            (result, flag),
+            "{:04X} with V1={:02X} V2={:02X}",
+            op,
+            vx,
+            vy
+        );
+        assert_eq!(emu.v[2], vy, "{:04X} changed Vy", op);
+    }
+}
+
+#[test]
+fn alu() {
+    check_alu(
+        Quirks::super_chip(),
+        &[
+            // 8xy0 - LD Vx, Vy
+            (0x8120, 0x12, 0x34, 0x34, 0xAA),
+            // 8xy4 - ADD Vx, Vy
+            (0x8124, 0x10, 0x20, 0x30, 0),
+            (0x8124, 0xFF, 0x01, 0x00, 1),
+            (0x8124, 0xFF, 0xFF, 0xFE, 1),
+            (0x8124, 0x80, 0x7F, 0xFF, 0),
+            // 8xy5 - SUB Vx, Vy
+            (0x8125, 0x30, 0x10, 0x20, 1),
+            (0x8125, 0x10, 0x10, 0x00, 1),
+            (0x8125, 0x10, 0x30, 0xE0, 0),
+            (0x8125, 0x00, 0xFF, 0x01, 0),
+            // 8xy6 - SHR Vx (SUPER-CHIP shifts Vx in place)
+            (0x8126, 0x03, 0xFF, 0x01, 1),
+            (0x8126, 0x02, 0xFF, 0x01, 0),
+            (0x8126, 0xFF, 0x00, 0x7F, 1),
+            (0x8126, 0x00, 0xFF, 0x00, 0),
+            // 8xy7 - SUBN Vx, Vy
+            (0x8127, 0x10, 0x30, 0x20, 1),
+            (0x8127, 0x10, 0x10, 0x00, 1),
+            (0x8127, 0x30, 0x10, 0xE0, 0),
+            // 8xyE - SHL Vx (SUPER-CHIP shifts Vx in place)
+            (0x812E, 0x81, 0x00, 0x02, 1),
+            (0x812E, 0x41, 0xFF, 0x82, 0),
+            (0x812E, 0xFF, 0x00, 0xFE, 1),
+            (0x812E, 0x00, 0xFF, 0x00, 0),
+        ],
+    );
+}
+
+#[test]
+fn logic() {
+    // 8xy1, 8xy2, 8xy3 leave VF alone, except on the COSMAC VIP
+    let cases: [AluCase; 3] = [
+        (0x8121, 0x0F, 0x30, 0x3F, 0xAA),
+        (0x8122, 0x3C, 0x0F, 0x0C, 0xAA),
+        (0x8123, 0x3C, 0x0F, 0x33, 0xAA),
+    ];
+    check_alu(Quirks::super_chip(), &cases);
+
+    let reset: Vec<AluCase> = cases
+        .iter()
+        .map(|&(op, vx, vy, result, _)| (op, vx, vy, result, 0))
+        .collect();
+    check_alu(Quirks::cosmac_vip(), &reset);
+}
+
+#[test]
+fn shifts_use_vy() {
+    // COSMAC VIP shifts Vy and stores the result in Vx
+    check_alu(
+        Quirks::cosmac_vip(),
+        &[
+            (0x8126, 0x00, 0x03, 0x01, 1),
+            (0x8126, 0xFF, 0x02, 0x01, 0),
+            (0x812E, 0x00, 0x81, 0x02, 1),
+            (0x812E, 0xFF, 0x41, 0x82, 0),
+        ],
+    );
+}
+
+#[test]
+fn flag_register_as_operand() {
+    // `(opcode, V1, VF, V1 after, VF after)`: the flag is written last, so it wins over the
+    // result when x is F, while y being F is read before anything is written
+    let cases: [(u16, u8, u8, u8, u8); 10] = [
+        (0x8F14, 0x01, 0xFF, 0x01, 1),
+        (0x8F14, 0x01, 0x10, 0x01, 0),
+        (0x81F4, 0xFF, 0x01, 0x00, 1),
+        (0x8F15, 0x01, 0x10, 0x01, 1),
+        (0x8F15, 0x20, 0x10, 0x20, 0),
+        (0x81F5, 0x10, 0x20, 0xF0, 0),
+        (0x8F17, 0x20, 0x10, 0x20, 1),
+        (0x8FF6, 0x00, 0x03, 0x00, 1),
+        (0x8FFE, 0x00, 0x40, 0x00, 0),
+        (0x81FE, 0x81, 0x00, 0x02, 1),
+    ];
+
+    for &(op, v1, vf, v1_after, vf_after) in &cases {
+        let emu = run(Quirks::super_chip(), &[(1, v1), (0xF, vf)], &[op]);
+        assert_eq!(
+            (emu.v[1], emu.v[0xF]),
+            (v1_after, vf_after),
+            "{:04X} with V1={:02X} VF={:02X}",
+            op,
+            v1,
+            vf
+        );
+    }
+}
+
+#[test]
+fn load_and_add_byte() {
+    // 6xkk, then 7xkk wrapping around without touching VF
+    let emu = run(
+        Quirks::default(),
+        &[(0xF, 0xAA)],
+        &[0x6AFE, 0x7A03, 0x6F10, 0x7F01],
+    );
+    assert_eq!(emu.v[0xA], 0x01);
+    assert_eq!(emu.v[0xF], 0x11);
+}
+
+#[test]
+fn skips() {
+    // `(opcode, V1, V2, whether next instruction is skipped)`
+    let cases: [(u16, u8, u8, bool); 8] = [
+        (0x3142, 0x42, 0, true),
+        (0x3142, 0x41, 0, false),
+        (0x4142, 0x42, 0, false),
+        (0x4142, 0x41, 0, true),
+        (0x5120, 0x07, 0x07, true),
+        (0x5120, 0x07, 0x08, false),
+        (0x9120, 0x07, 0x07, false),
+        (0x9120, 0x07, 0x08, true),
+    ];
+
+    for &(op, v1, v2, skipped) in &cases {
+        let emu = run(Quirks::default(), &[(1, v1), (2, v2)], &[op]);
+        let expected = ROM_OFFSET as u16 + if skipped { 4 } else { 2 };
+        assert_eq!(
+            emu.pc, expected,
+            "{:04X} with V1={:02X} V2={:02X}",
+            op, v1, v2
+        );
+    }
+}
+
+#[test]
+fn skip_keys() {
+    for pressed in [false, true] {
+        for (op, skips_when_pressed) in [(0xE19E, true), (0xE1A1, false)] {
+            let mut emu = setup(Quirks::default(), &[(1, 0xB)], &[op]);
+            if pressed {
+                emu.key_down(0xB);
+            }
+            steps(&mut emu, 1);
+
+            let skipped = pressed == skips_when_pressed;
+            let expected = ROM_OFFSET as u16 + if skipped { 4 } else { 2 };
+            assert_eq!(emu.pc, expected, "{:04X} with key pressed: {}", op, pressed);
+        }
+    }
+}
+
+#[test]
+fn wait_key() {
+    // Fx0A stops until a key goes down, then stores it in Vx
+    let mut emu = setup(Quirks::default(), &[], &[0xF50A, 0x1202]);
+    steps(&mut emu, 1);
+    assert!(emu.paused);
+    assert_eq!(emu.step(), Ok(StepOutcome::WaitingForKey));
+
+    emu.key_down(0x7);
+    assert!(!emu.paused);
+    assert_eq!(emu.v[5], 0x7);
+    assert_eq!(emu.pc, 0x202);
+}
+
+#[test]
+fn jumps_and_calls() {
+    // 2nnn, 00EE, 1nnn
+    let mut emu = setup(
+        Quirks::default(),
+        &[],
+        &[0x2206, 0x1208, 0x0000, 0x00EE, 0x0000],
+    );
+    steps(&mut emu, 1);
+    assert_eq!((emu.pc, emu.sp, emu.stack[0]), (0x206, 1, 0x202));
+    steps(&mut emu, 2);
+    assert_eq!((emu.pc, emu.sp), (0x208, 0));
+
+    // Bnnn jumps to nnn + V0, or to xnn + Vx on SUPER-CHIP
+    let emu = run(Quirks::cosmac_vip(), &[(0, 0x10), (3, 0x20)], &[0xB300]);
+    assert_eq!(emu.pc, 0x310);
+    let emu = run(Quirks::super_chip(), &[(0, 0x10), (3, 0x20)], &[0xB300]);
+    assert_eq!(emu.pc, 0x320);
+}
+
+#[test]
+fn program_counter_wraps() {
+    // `(opcode at 0xFFFE, opcode at 0, PC after)`: plain, skipping, skipping `F000 nnnn`
+    let cases = [
+        (0x6000, 0x6000, 0x0000),
+        (0x3000, 0x6000, 0x0002),
+        (0x3000, 0xF000, 0x0004),
+    ];
+
+    for &(op, first, pc) in &cases {
+        let mut emu = setup(Quirks::xo_chip(), &[], &[]);
+        emu.memory[0xFFFE..].copy_from_slice(&(op as u16).to_be_bytes());
+        emu.memory[0..2].copy_from_slice(&(first as u16).to_be_bytes());
+        emu.pc = 0xFFFE;
+        steps(&mut emu, 1);
+        assert_eq!(emu.pc, pc, "{:04X} before {:04X}", op, first);
+    }
+
+    // F000 nnnn with nnnn wrapped around to the start of memory
+    let mut emu = setup(Quirks::xo_chip(), &[], &[]);
+    emu.memory[0xFFFE..].copy_from_slice(&[0xF0, 0x00]);
+    emu.memory[0..2].copy_from_slice(&[0x12, 0x34]);
+    emu.pc = 0xFFFE;
+    steps(&mut emu, 1);
+    assert_eq!((emu.pc, emu.i), (0x0002, 0x1234));
+
+    let mut emu = setup(Quirks::default(), &[], &[]);
+    emu.memory[0xFFFE..].copy_from_slice(&[0xE0, 0xA0]);
+    emu.pc = 0xFFFE;
+    assert_eq!(
+        emu.step(),
+        Err(EmulatorError::UnknownOpcode {
+            op: 0xE0A0,
+            pc: 0xFFFE
+        })
+    );
+}
+
+#[test]
+fn stack_errors() {
+    let mut emu = setup(Quirks::default(), &[], &[0x00EE]);
+    assert!(emu.step().is_err());
+    assert_eq!(emu.pc, ROM_OFFSET as u16);
+
+    // Calls itself until the stack is full
+    let mut emu = setup(Quirks::default(), &[], &[0x2200]);
+    steps(&mut emu, 16);
+    assert!(emu.step().is_err());
+
+    // Errors at the end of memory name the instruction's address, not the wrapped PC
+    let cases = [
+        (0x00EE, EmulatorError::StackUnderflow { pc: 0xFFFE }),
+        (0x2200, EmulatorError::StackOverflow { pc: 0xFFFE }),
+    ];
+    for (op, err) in cases {
+        let mut emu = setup(Quirks::default(), &[], &[]);
+        emu.memory[0xFFFE..].copy_from_slice(&(op as u16).to_be_bytes());
+        if op == 0x2200 {
+            emu.sp = emu.stack.len() as u8;
+        }
+        emu.pc = 0xFFFE;
+        assert_eq!(emu.step(), Err(err), "{:04X}", op);
+    }
+}
+
+#[test]
+fn index_register() {
+    // Annn, then Fx1E
+    let emu = run(Quirks::default(), &[(4, 0x30)], &[0xA123, 0xF41E]);
+    assert_eq!(emu.i, 0x153);
+
+    // Fx29 points at the font sprite for the digit
+    let emu = run(Quirks::default(), &[(2, 0xA)], &[0xF229]);
+    assert_eq!(emu.i, 0xA * 5);
+    assert_eq!(
+        &emu.memory[emu.i as usize..emu.i as usize + 5],
+        &[0xF0, 0x90, 0xF0, 0x90, 0x90]
+    );
+}
+
+#[test]
+fn timers() {
+    // Fx15, Fx18, then Fx07
+    let emu = run(
+        Quirks::default(),
+        &[(3, 0x3C), (4, 0x05)],
+        &[0xF315, 0xF418, 0xF807],
+    );
+    assert_eq!((emu.dt, emu.st, emu.v[8]), (0x3C, 0x05, 0x3C));
+}
+
+#[test]
+fn random() {
+    // Cxkk is masked with kk
+    let emu = run(Quirks::default(), &[(1, 0xFF)], &[0xC100]);
+    assert_eq!(emu.v[1], 0);
+
+    for _ in 0..32 {
+        let emu = run(Quirks::default(), &[], &[0xC10F]);
+        assert_eq!(emu.v[1] & 0xF0, 0);
+    }
+}
+
+#[test]
+fn bcd() {
+    let cases: [(u8, [u8; 3]); 6] = [
+        (0, [0, 0, 0]),
+        (9, [0, 0, 9]),
+        (10, [0, 1, 0]),
+        (99, [0, 9, 9]),
+        (100, [1, 0, 0]),
+        (255, [2, 5, 5]),
+    ];
+
+    for &(value, digits) in &cases {
+        // Vx being VF works like any other register
+        for x in [0x3, 0xF] {
+            let emu = run(
+                Quirks::default(),
+                &[(x, value)],
+                &[0xA300, 0xF033 | (x as u16) << 8],
+            );
+            assert_eq!(&emu.memory[0x300..0x303], &digits, "{} in V{:X}", value, x);
+            assert_eq!(emu.i, 0x300);
+        }
+    }
+}
+
+#[test]
+fn store_and_load_registers() {
+    // `(x, I after on COSMAC VIP)`, x being the last register, not its value
+    for &(x, vip_i) in &[(0usize, 0x301), (5, 0x306), (0xF, 0x310)] {
+        let regs: Vec<(usize, u8)> = (0..16).map(|reg| (reg, 0x10 + reg as u8)).collect();
+        let store = 0xF055 | (x as u16) << 8;
+
+        let profiles = [
+            (Quirks::cosmac_vip(), vip_i),
+            (Quirks::chip48(), vip_i - 1),
+            (Quirks::super_chip(), 0x300),
+        ];
+        for (quirks, i) in profiles {
+            // Fx55 stores V0 through Vx, leaving the following byte alone
+            let mut emu = setup(quirks, &regs, &[0xA300, store]);
+            emu.memory[0x300 + x + 1] = 0xEE;
+            steps(&mut emu, 2);
+
+            let expected: Vec<u8> = (0..=x as u8).map(|reg| 0x10 + reg).collect();
+            assert_eq!(
+                &emu.memory[0x300..=0x300 + x],
+                &expected[..],
+                "{:04X}",
+                store
+            );
+            if x < 0xF {
+                assert_eq!(emu.memory[0x300 + x + 1], 0xEE, "{:04X}", store);
+            }
+            assert_eq!(emu.i, i, "{:04X} with {:?}", store, quirks);
+
+            // Fx65 loads them back after clearing, leaving registers past Vx alone
+            let load = 0xF065 | (x as u16) << 8;
+            let mut emu = setup(quirks, &[], &[0xA300, load]);
+            emu.memory[0x300..0x310].copy_from_slice(&[0x20; 16]);
+            steps(&mut emu, 2);
+
+            for reg in 0..16 {
+                let expected = if reg <= x { 0x20 } else { 0 };
+                assert_eq!(emu.v[reg], expected, "V{:X} after {:04X}", reg, load);
+            }
+            assert_eq!(emu.i, i, "{:04X} with {:?}", load, quirks);
+        }
+    }
+}
+
+#[test]
+fn store_and_load_at_end_of_memory() {
+    // All 16 registers fit right up to 0xFFFF, `I` then wraps around
+    for (quirks, i) in [(Quirks::cosmac_vip(), 0x0000), (Quirks::chip48(), 0xFFFF)] {
+        for op in [0xFF55, 0xFF65] {
+            let regs: Vec<(usize, u8)> = (0..16).map(|reg| (reg, reg as u8)).collect();
+            let mut emu = setup(quirks, &regs, &[op]);
+            emu.i = 0xFFF0;
+            steps(&mut emu, 1);
+
+            assert_eq!(emu.i, i, "{:04X} with {:?}", op, quirks);
+            if op == 0xFF55 {
+                assert_eq!(emu.memory[0xFFFF], 0xF);
+            } else {
+                assert_eq!(emu.v[0xF], 0);
+            }
+        }
+    }
+
+    // One more does not
+    let mut emu = setup(Quirks::cosmac_vip(), &[], &[0xFF55]);
+    emu.i = 0xFFF1;
+    assert_eq!(
+        emu.step(),
+        Err(EmulatorError::MemoryOutOfBounds { addr: 0x10000 })
+    );
+    assert_eq!(emu.i, 0xFFF1);
+}
+
+/// Lit pixels of the low resolution display as (x, y)
+fn lit(emu: &Emulator) -> Vec<(usize, usize)> {
+    (0..emu.display.len())
+        .filter(|idx| emu.display[*idx] != 0)
+        .map(|idx| (idx % COLS, idx / COLS))
+        .collect()
+}
+
+#[test]
+fn draw() {
+    // Font sprite for 1 (0x20, 0x60, 0x20, 0x20, 0x70) at (3, 4)
+    let emu = run(
+        Quirks::default(),
+        &[(1, 3), (2, 4), (0xA, 1)],
+        &[0xFA29, 0xD125],
+    );
+    assert_eq!(
+        lit(&emu),
+        vec![
+            (5, 4),
+            (4, 5),
+            (5, 5),
+            (5, 6),
+            (5, 7),
+            (4, 8),
+            (5, 8),
+            (6, 8),
+        ]
+    );
+    assert_eq!(emu.v[0xF], 0);
+}
+
+#[test]
+fn draw_collision() {
+    // Drawing the same sprite twice erases it, colliding
+    let emu = run(
+        Quirks::default(),
+        &[(1, 10), (2, 10)],
+        &[0xA000, 0xD125, 0xD125],
+    );
+    assert!(lit(&emu).is_empty());
+    assert_eq!(emu.v[0xF], 1);
+
+    // Drawing next to it does not collide, VF is reset even if it was set
+    let emu = run(
+        Quirks::default(),
+        &[(1, 10), (2, 10), (3, 20), (0xF, 1)],
+        &[0xA000, 0xD125, 0xD325],
+    );
+    assert_eq!(emu.v[0xF], 0);
+
+    // Overlapping in one pixel only (top right of 0 with top left of the same sprite moved
+    // right by 3) is still a collision, the rest stays lit
+    let emu = run(
+        Quirks::default(),
+        &[(1, 10), (2, 10), (3, 13)],
+        &[0xA000, 0xD121, 0xD321],
+    );
+    assert_eq!(emu.v[0xF], 1);
+    assert_eq!(
+        lit(&emu),
+        vec![(10, 10), (11, 10), (12, 10), (14, 10), (15, 10), (16, 10)]
+    );
+}
+
+#[test]
+fn draw_at_edges() {
+    // Sprite for 0 is 4 pixels wide, starting at x = 62 leaves 2 columns on screen
+    let regs = [(1, 62), (2, 0)];
+    let program = [0xA000, 0xD121];
+
+    // COSMAC VIP clips
+    let emu = run(Quirks::cosmac_vip(), &regs, &program);
+    assert_eq!(lit(&emu), vec![(62, 0), (63, 0)]);
+
+    // XO-CHIP wraps around
+    let emu = run(Quirks::xo_chip(), &regs, &program);
+    assert_eq!(lit(&emu), vec![(0, 0), (1, 0), (62, 0), (63, 0)]);
+
+    // Starting position always wraps
+    let emu = run(Quirks::cosmac_vip(), &[(1, 64 + 2), (2, 32 + 1)], &program);
+    assert_eq!(lit(&emu), vec![(2, 1), (3, 1), (4, 1), (5, 1)]);
+}
+
+#[test]
+fn draw_waits_for_vblank() {
+    let mut emu = setup(Quirks::cosmac_vip(), &[], &[0xA000, 0xD001, 0x6001]);
+    assert_eq!(emu.step(), Ok(StepOutcome::Executed));
+    assert_eq!(emu.step(), Ok(StepOutcome::Executed));
+    assert_eq!(emu.step(), Ok(StepOutcome::WaitingForVblank));
+
+    let mut emu = setup(Quirks::super_chip(), &[], &[0xA000, 0xD001, 0x6001]);
+    steps(&mut emu, 3);
+    assert_eq!(emu.v[0], 1);
+}
+
+#[test]
+fn clear_display() {
+    let emu = run(Quirks::default(), &[], &[0xA000, 0xD005, 0x00E0]);
+    assert!(lit(&emu).is_empty());
+}
@@ -0,0 +1,107 @@
+//! Rewind buffer tests: every frame pushed comes back byte for byte, including frames of
+//! different sizes, and failed rewinds leave the buffer in step with the emulator.
+
+use std::time::Duration;
+
+use chip8_core::{emu::Emulator, error::StateError, rewind::Rewind};
+
+const PONG: &[u8] = include_bytes!("../../compiler/roms/pong.ch8");
+
+fn pong() -> Emulator {
+    let mut emu = Emulator::with_seed(3);
+    emu.load_sprites();
+    emu.load_rom(PONG).unwrap();
+    emu
+}
+
+/// Rewinds through the buffer, checking every state against `frames`, newest last
+fn check_rewinds(rewind: &mut Rewind, emu: &mut Emulator, frames: &[Vec<u8>]) {
+    for (idx, expected) in frames.iter().enumerate().rev().skip(1) {
+        assert_eq!(rewind.rewind(emu), Ok(true), "frame {}", idx);
+        assert!(emu.save_state() == *expected, "frame {} differs", idx);
+    }
+
+    assert_eq!(rewind.rewind(emu), Ok(false));
+    assert!(rewind.is_empty());
+}
+
+#[test]
+fn frames_come_back() {
+    let mut emu = pong();
+    let mut rewind = Rewind::new(200);
+    let mut frames = Vec::new();
+
+    for frame in 0..120 {
+        match frame {
+            30 => emu.key_down(1),
+            60 => emu.key_up(1),
+            _ => {}
+        }
+        emu.run_for(Duration::from_nanos(16_666_667)).unwrap();
+
+        rewind.push(&emu);
+        frames.push(emu.save_state());
+    }
+    assert_eq!(rewind.len(), 119);
+
+    check_rewinds(&mut rewind, &mut emu, &frames);
+}
+
+#[test]
+fn capacity_drops_oldest() {
+    let mut emu = pong();
+    let mut rewind = Rewind::new(10);
+    let mut frames = Vec::new();
+
+    for _ in 0..30 {
+        emu.run_for(Duration::from_nanos(16_666_667)).unwrap();
+        rewind.push(&emu);
+        frames.push(emu.save_state());
+    }
+    assert_eq!(rewind.len(), 10);
+
+    check_rewinds(&mut rewind, &mut emu, &frames[19..]);
+}
+
+#[test]
+fn state_size_changes() {
+    // The display grows 4 times in high resolution, and so does the state
+    let mut emu = pong();
+    let mut rewind = Rewind::new(10);
+    let mut frames = Vec::new();
+
+    for hires in [false, true, true, false, true] {
+        if emu.hires != hires {
+            emu.set_hires(hires);
+        }
+        let last = emu.display.len() - 1;
+        emu.display[last] ^= 1;
+        emu.memory[0xFFFF] ^= 0xA5;
+
+        rewind.push(&emu);
+        frames.push(emu.save_state());
+    }
+    assert!(frames[1].len() > frames[0].len());
+
+    check_rewinds(&mut rewind, &mut emu, &frames);
+}
+
+#[test]
+fn failed_rewind_keeps_buffer() {
+    let mut emu = pong();
+    let mut rewind = Rewind::new(10);
+    let mut frames = Vec::new();
+
+    for _ in 0..5 {
+        emu.run_for(Duration::from_nanos(16_666_667)).unwrap();
+        rewind.push(&emu);
+        frames.push(emu.save_state());
+    }
+
+    let mut other = Emulator::with_seed(3);
+    other.load_rom(&[0x12, 0x00]).unwrap();
+    assert_eq!(rewind.rewind(&mut other), Err(StateError::RomMismatch));
+    assert_eq!(rewind.len(), 4);
+
+    check_rewinds(&mut rewind, &mut emu, &frames);
+}
@@ -0,0 +1,137 @@
+//! Save state tests: round trips, and rejecting states that are foreign, truncated or hold
+//! values the emulator could never be in.
+
+use std::time::Duration;
+
+use chip8_core::{emu::Emulator, error::StateError};
+
+const PONG: &[u8] = include_bytes!("../../compiler/roms/pong.ch8");
+
+/// Pong run for a while with a paddle moving, stopped mid-frame
+fn running() -> Emulator {
+    let mut emu = Emulator::with_seed(7);
+    emu.load_sprites();
+    emu.load_rom(PONG).unwrap();
+
+    emu.run_for(Duration::from_millis(500)).unwrap();
+    emu.key_down(1);
+    emu.run_for(Duration::from_micros(250_123)).unwrap();
+    emu
+}
+
+/// Emulator with pong loaded, ready to load states into
+fn fresh() -> Emulator {
+    let mut emu = Emulator::with_seed(0);
+    emu.load_rom(PONG).unwrap();
+    emu
+}
+
+#[test]
+fn round_trip() {
+    let mut emu = running();
+    let state = emu.save_state();
+
+    let mut loaded = fresh();
+    loaded.load_state(&state).unwrap();
+    assert_eq!(loaded.save_state(), state);
+
+    // Both carry on exactly the same way, random numbers included
+    for emu in [&mut emu, &mut loaded] {
+        emu.key_up(1);
+        emu.run_for(Duration::from_secs(2)).unwrap();
+    }
+    assert_eq!(loaded.save_state(), emu.save_state());
+    assert_eq!(loaded.display, emu.display);
+
+    // Going back in time on the same emulator
+    emu.load_state(&state).unwrap();
+    assert_eq!(emu.save_state(), state);
+}
+
+#[test]
+fn foreign_states() {
+    let state = running().save_state();
+    let mut emu = fresh();
+    let before = emu.save_state();
+
+    let mut bad = state.clone();
+    bad[0] = b'X';
+    assert_eq!(emu.load_state(&bad), Err(StateError::BadMagic));
+
+    let mut bad = state.clone();
+    bad[4] = 99;
+    assert_eq!(
+        emu.load_state(&bad),
+        Err(StateError::UnsupportedVersion(99))
+    );
+
+    let mut other = Emulator::with_seed(0);
+    other.load_rom(&PONG[..PONG.len() - 1]).unwrap();
+    assert_eq!(other.load_state(&state), Err(StateError::RomMismatch));
+
+    assert_eq!(emu.save_state(), before, "failed loads changed the state");
+}
+
+#[test]
+fn truncated_states() {
+    let state = running().save_state();
+    let mut emu = fresh();
+
+    for len in [
+        0,
+        3,
+        4,
+        12,
+        state.len() / 2,
+        state.len() - 8,
+        state.len() - 1,
+    ] {
+        assert!(emu.load_state(&state[..len]).is_err(), "{} bytes", len);
+    }
+
+    let mut longer = state.clone();
+    longer.push(0);
+    assert_eq!(emu.load_state(&longer), Err(StateError::Corrupted));
+}
+
+/// Description of a change to an Emulator, and the change
+type Change = (&'static str, fn(&mut Emulator));
+
+#[test]
+fn invalid_values() {
+    // Every change makes a state the emulator can not get into by running
+    let changes: [Change; 7] = [
+        ("stack pointer past the stack", |emu| emu.sp = 0x11),
+        ("pixel in a fourth plane", |emu| emu.display[5] = 4),
+        ("fourth plane selected", |emu| emu.planes = 7),
+        ("paused without a key to wait for", |emu| emu.paused = true),
+        ("waiting for key 0x10", |emu| {
+            emu.paused = true;
+            emu.unpause_next = Some(0x10);
+        }),
+        ("time before the last timer update", |emu| emu.clock.now = 0),
+        ("timer updates overflowing", |emu| {
+            emu.clock.timer_ticks = u64::MAX
+        }),
+    ];
+    let mut emu = fresh();
+
+    for (what, change) in &changes {
+        let mut bad = running();
+        change(&mut bad);
+        assert_eq!(
+            emu.load_state(&bad.save_state()),
+            Err(StateError::Corrupted),
+            "{}",
+            what
+        );
+    }
+
+    // Booleans are stored as 0 or 1, `hires` follows the magic, version, hash, memory,
+    // registers, stack and stack pointer
+    let mut bad = running().save_state();
+    let hires = 4 + 1 + 8 + 0x10000 + 0x10 + 0x10 * 2 + 1;
+    assert_eq!(bad[hires], 0);
+    bad[hires] = 2;
+    assert_eq!(emu.load_state(&bad), Err(StateError::Corrupted));
+}
@@ -0,0 +1,65 @@
+//! Trace tests: the exact line format traces are diffed in, and recording only the address
+//! ranges asked for while still counting every instruction.
+
+use chip8_core::{
+    emu::{Emulator, StepOutcome},
+    trace::{TraceEntry, Tracer},
+};
+
+/// `LD V0, 5`, `LD I, 0x2EA`, `ADD V0, V1`, `CALL 0x208`, then `JP 0x206` calling again
+const ROM: [u16; 5] = [0x6005, 0xA2EA, 0x8014, 0x2208, 0x1206];
+
+/// Runs `count` instructions with given tracer, V1 starting out as 0xFF
+fn trace(tracer: Tracer, count: usize) -> Vec<TraceEntry> {
+    let rom: Vec<u8> = ROM.iter().flat_map(|op| op.to_be_bytes()).collect();
+    let mut emu = Emulator::with_seed(0);
+    emu.load_rom(&rom).unwrap();
+    emu.v[1] = 0xFF;
+    emu.tracer = Some(tracer);
+
+    for _ in 0..count {
+        assert_eq!(emu.step(), Ok(StepOutcome::Executed));
+    }
+
+    let tracer = emu.tracer.as_mut().unwrap();
+    assert_eq!(tracer.cycles, count as u64);
+    tracer.drain().collect()
+}
+
+#[test]
+fn line_format() {
+    let lines: Vec<String> = trace(Tracer::new(), 4)
+        .iter()
+        .map(|entry| entry.to_string())
+        .collect();
+
+    // Count, PC, opcode, disassembly padded to 24, I, SP, then the V registers changed
+    assert_eq!(
+        lines,
+        vec![
+            "00000000 0200 6005 ld V0, #5                I=0000 SP=0 V0=05",
+            "00000001 0202 A2EA ld I, #746               I=02EA SP=0",
+            "00000002 0204 8014 add V0, V1               I=02EA SP=0 V0=04 VF=01",
+            "00000003 0206 2208 call #0x0208             I=02EA SP=1",
+        ]
+    );
+}
+
+#[test]
+fn address_ranges() {
+    let mut tracer = Tracer::new();
+    tracer.ranges = vec![(0x202, 0x203), (0x206, 0x206)];
+    assert!(!tracer.traces(0x201));
+    assert!(tracer.traces(0x202) && tracer.traces(0x203));
+    assert!(!tracer.traces(0x204) && !tracer.traces(0x207));
+
+    // Counts go on through instructions left out
+    let recorded: Vec<(u64, u16)> = trace(tracer, 8)
+        .iter()
+        .map(|entry| (entry.cycle, entry.pc))
+        .collect();
+    assert_eq!(
+        recorded,
+        vec![(1, 0x202), (3, 0x206), (5, 0x206), (7, 0x206)]
+    );
+}
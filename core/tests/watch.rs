@@ -0,0 +1,171 @@
+//! Watchpoint tests: every instruction accessing data memory stops on the kinds of watchpoints
+//! it should, and the hit names the instruction that made the access.
+
+use chip8_core::{
+    emu::{Emulator, StepOutcome},
+    instruction::Instruction,
+    watch::{Access, WatchHit, Watchpoint},
+};
+
+/// Where `I` points, away from the program
+const DATA: u16 = 0x300;
+
+/// Emulator running `LD V0, 123`, `LD V1, 2`, `LD V2, 1`, `LD I, DATA` then `op` at 0x208,
+/// with `memory` at `DATA` and a watchpoint
+fn setup(op: u16, memory: &[u8], watchpoint: Watchpoint) -> Emulator {
+    let program = [0x607B, 0x6102, 0x6201, 0xA000 | DATA, op];
+    let rom: Vec<u8> = program.iter().flat_map(|op| op.to_be_bytes()).collect();
+
+    let mut emu = Emulator::with_seed(0);
+    emu.load_sprites();
+    emu.load_rom(&rom).unwrap();
+    emu.memory[DATA as usize..DATA as usize + memory.len()].copy_from_slice(memory);
+    emu.watchpoints.push(watchpoint);
+
+    for _ in 0..4 {
+        assert_eq!(emu.step(), Ok(StepOutcome::Executed));
+    }
+    emu
+}
+
+fn watch(access: Access, start: u16, end: u16) -> Watchpoint {
+    Watchpoint { start, end, access }
+}
+
+/// Runs `op` and returns the watchpoint hit, checking the step reported it
+fn hit(op: u16, memory: &[u8], watchpoint: Watchpoint) -> Option<WatchHit> {
+    let mut emu = setup(op, memory, watchpoint);
+    let outcome = emu.step().unwrap();
+
+    assert_eq!(
+        outcome == StepOutcome::Watchpoint,
+        emu.watch_hit.is_some(),
+        "{:04X}",
+        op
+    );
+    if let Some(hit) = emu.watch_hit {
+        assert_eq!(hit.pc, 0x208);
+        assert_eq!(hit.instruction, Instruction::decode(op));
+    }
+    emu.watch_hit
+}
+
+/// Expected hit on `watchpoint` at `addr` by `op` at 0x208
+fn expected(op: u16, watchpoint: Watchpoint, addr: u16, old: u8, new: Option<u8>) -> WatchHit {
+    WatchHit {
+        watchpoint,
+        addr,
+        old,
+        new,
+        pc: 0x208,
+        instruction: Instruction::decode(op),
+    }
+}
+
+#[test]
+fn bcd() {
+    // Fx33 writes 1, 2 and 3
+    let op = 0xF033;
+    let write = watch(Access::Write, DATA + 1, DATA + 2);
+    assert_eq!(
+        hit(op, &[1, 2, 3], write),
+        Some(expected(op, write, DATA + 1, 2, Some(2)))
+    );
+
+    let change = watch(Access::Change, DATA, DATA + 2);
+    assert_eq!(hit(op, &[1, 2, 3], change), None);
+    assert_eq!(
+        hit(op, &[1, 2, 0], change),
+        Some(expected(op, change, DATA + 2, 0, Some(3)))
+    );
+
+    assert_eq!(hit(op, &[], watch(Access::Read, DATA, DATA + 2)), None);
+}
+
+#[test]
+fn store_registers() {
+    // Fx55 writes V0 to V2: 123, 2, 1
+    let op = 0xF255;
+    let write = watch(Access::Write, DATA, DATA);
+    assert_eq!(
+        hit(op, &[9], write),
+        Some(expected(op, write, DATA, 9, Some(123)))
+    );
+
+    // Only the last byte changes
+    let change = watch(Access::Change, DATA, DATA + 0xF);
+    assert_eq!(
+        hit(op, &[123, 2, 0], change),
+        Some(expected(op, change, DATA + 2, 0, Some(1)))
+    );
+    assert_eq!(hit(op, &[123, 2, 1], change), None);
+
+    // Past the registers stored
+    assert_eq!(hit(op, &[], watch(Access::Write, DATA + 3, DATA + 3)), None);
+    assert_eq!(hit(op, &[], watch(Access::Read, DATA, DATA + 2)), None);
+}
+
+#[test]
+fn load_registers() {
+    // Fx65 reads V0 and V1
+    let op = 0xF165;
+    let read = watch(Access::Read, DATA + 1, DATA + 1);
+    assert_eq!(
+        hit(op, &[5, 6], read),
+        Some(expected(op, read, DATA + 1, 6, None))
+    );
+
+    assert_eq!(hit(op, &[], watch(Access::Read, DATA + 2, DATA + 2)), None);
+    assert_eq!(hit(op, &[], watch(Access::Write, DATA, DATA + 1)), None);
+    assert_eq!(hit(op, &[], watch(Access::Change, DATA, DATA + 1)), None);
+}
+
+#[test]
+fn draw() {
+    // Dxyn reads the 2 sprite rows, and nothing else
+    let op = 0xD012;
+    let read = watch(Access::Read, DATA, DATA + 1);
+    assert_eq!(
+        hit(op, &[0xF0, 0x0F], read),
+        Some(expected(op, read, DATA, 0xF0, None))
+    );
+
+    assert_eq!(hit(op, &[], watch(Access::Read, DATA + 2, DATA + 2)), None);
+    assert_eq!(hit(op, &[], watch(Access::Write, DATA, DATA + 1)), None);
+    assert_eq!(hit(op, &[], watch(Access::Change, DATA, DATA + 1)), None);
+}
+
+#[test]
+fn hit_lasts_one_instruction() {
+    let mut emu = setup(0xF033, &[], watch(Access::Write, DATA, DATA));
+    assert_eq!(emu.step(), Ok(StepOutcome::Watchpoint));
+    assert!(emu.watch_hit.is_some());
+
+    // Runs on into zeroed memory, 0000 being a plain `SYS`
+    assert_eq!(emu.step(), Ok(StepOutcome::Executed));
+    assert_eq!(emu.watch_hit, None);
+}
+
+#[test]
+fn instruction_overwriting_itself() {
+    // Fx55 storing over itself still names the instruction as it was
+    let mut emu = Emulator::with_seed(0);
+    emu.load_rom(&[0xA2, 0x02, 0xF3, 0x55]).unwrap();
+    emu.v[..4].copy_from_slice(&[0x12, 0x34, 0x56, 0x78]);
+    let write = watch(Access::Write, 0x203, 0x203);
+    emu.watchpoints.push(write);
+
+    assert_eq!(emu.step(), Ok(StepOutcome::Executed));
+    assert_eq!(emu.step(), Ok(StepOutcome::Watchpoint));
+    assert_eq!(
+        emu.watch_hit,
+        Some(WatchHit {
+            watchpoint: write,
+            addr: 0x203,
+            old: 0x55,
+            new: Some(0x34),
+            pc: 0x202,
+            instruction: Instruction::decode(0xF355),
+        })
+    );
+}
@@ -0,0 +1,287 @@
+use std::path::PathBuf;
+
+use chip8_core::{
+    audio::{Beeper, Waveform},
+    clock::{DEFAULT_IPS, TIMER_HZ},
+    quirks::Quirks,
+};
+
+/// Usage text printed for `--help` and on invalid arguments
+pub const USAGE: &str = "\
+Usage: chip8 [OPTIONS] <ROM>
+
+Options:
+  --ips <N>            Instructions executed per second (default: 600)
+  --speed <N>          Instructions executed per frame, same as --ips N*60
+  --scale <N>          Window scale factor (default: 10)
+  --paused             Start paused, press Space to resume
+  --theme <NAME>       Colour theme: default, amber, phosphor, lcd, octo or one from the config file
+  --config <FILE>      Read settings and themes from FILE
+  --quirks <PROFILE>   Quirks profile to emulate: cowgod, vip, chip48, schip or xochip (default: cowgod)
+  --seed <N>           Seed for the random number generator (default: random)
+  --decode-cache       Decode all of memory up front, for faster long runs
+  --debug              Start stopped in the debugger, reading commands from the terminal
+  --trace <FILE>       Write a line per executed instruction to FILE
+  --trace-range <A-B>  Only trace instructions from address A to B, e.g. 0x200-0x2ff (repeatable)
+  --headless           Run without opening a window, print display and registers at the end
+  --frames <N>         Stop after N frames, run as fast as possible (headless only)
+  --input <FILE>       Press keys as scripted in FILE, lines of `<frame> down|up <key>` (headless only)
+  --save-movie <FILE>  Record key presses into a movie FILE, written when the emulator stops
+  --play <FILE>        Replay a movie, using its seed, speed and quirks
+  --dump <FILE>        Write final display to FILE as PNG (.png), PBM (.pbm) or text (headless only)
+  --record <FILE>      Record the display into an animated GIF, written when the emulator stops
+  --capture-scale <N>  Scale factor of screenshots, recordings and PNG dumps (default: 4)
+  --mute               Do not play any sound
+  --wav <FILE>         Record sound into a WAV file instead of playing it
+  --tone <HZ>          Frequency of the beep (default: 440)
+  --volume <N>         Volume of the beep, from 0 to 1 (default: 0.25)
+  --waveform <SHAPE>   Shape of the beep: square, triangle, sawtooth or sine (default: square)
+  -h, --help           Print this help";
+
+/// Options passed on the command line
+#[derive(Debug)]
+pub struct Options {
+    /// Path to the ROM to load
+    pub rom: PathBuf,
+    /// Instructions executed per second
+    pub ips: u32,
+    /// Window scale factor
+    pub scale: f64,
+    /// Whether to start paused
+    pub paused: bool,
+    /// Name of the colour theme to start with
+    pub theme: Option<String>,
+    /// Config file to read
+    pub config: Option<PathBuf>,
+    /// Quirks profile to use
+    pub quirks: Quirks,
+    /// Seed for the random number generator
+    pub seed: Option<u64>,
+    /// Whether to enable the decode cache
+    pub decode_cache: bool,
+    /// Whether to run under the debugger
+    pub debug: bool,
+    /// File to write the execution trace to
+    pub trace: Option<PathBuf>,
+    /// Address ranges to trace, all if empty
+    pub trace_ranges: Vec<(u16, u16)>,
+    /// Whether to run without a window
+    pub headless: bool,
+    /// Number of frames to run for when headless
+    pub frames: Option<u64>,
+    /// Input script to follow when headless
+    pub input: Option<PathBuf>,
+    /// Movie file to record key presses into
+    pub save_movie: Option<PathBuf>,
+    /// Movie file to replay
+    pub play: Option<PathBuf>,
+    /// File to write final display to when headless
+    pub dump: Option<PathBuf>,
+    /// GIF file to record the display into
+    pub record: Option<PathBuf>,
+    /// Scale factor of captured images
+    pub capture_scale: usize,
+    /// Whether to play no sound
+    pub mute: bool,
+    /// WAV file to record sound into
+    pub wav: Option<PathBuf>,
+    /// Synthesizer for the beep
+    pub beeper: Beeper,
+}
+
+/// Result of parsing the command line
+pub enum Command {
+    Run(Box<Options>),
+    Help,
+}
+
+/// Parses command line arguments (without the program name)
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut args = args.into_iter();
+    let mut rom = None;
+    let mut ips = DEFAULT_IPS;
+    let mut scale = 10f64;
+    let mut paused = false;
+    let mut theme = None;
+    let mut config = None;
+    let mut quirks = Quirks::default();
+    let mut seed = None;
+    let mut decode_cache = false;
+    let mut debug = false;
+    let mut trace = None;
+    let mut trace_ranges = Vec::new();
+    let mut headless = false;
+    let mut frames = None;
+    let mut input = None;
+    let mut save_movie = None;
+    let mut play = None;
+    let mut dump = None;
+    let mut record = None;
+    let mut capture_scale = 4;
+    let mut mute = false;
+    let mut wav = None;
+    let mut beeper = Beeper::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--ips" | "--speed" => {
+                let value = value_of(&mut args, &arg)?;
+                ips = match value.parse::<u32>() {
+                    Ok(n) if n > 0 && arg == "--ips" => n,
+                    Ok(n) if n > 0 => n.saturating_mul(TIMER_HZ as u32),
+                    _ => return Err(format!("invalid value '{}' for {}", value, arg)),
+                };
+            }
+            "--scale" => {
+                let value = value_of(&mut args, &arg)?;
+                scale = match value.parse::<f64>() {
+                    Ok(scale) if scale > 0.0 => scale,
+                    _ => return Err(format!("invalid value '{}' for --scale", value)),
+                };
+            }
+            "--paused" => paused = true,
+            "--theme" => theme = Some(value_of(&mut args, &arg)?),
+            "--config" => config = Some(PathBuf::from(value_of(&mut args, &arg)?)),
+            "--quirks" => {
+                let value = value_of(&mut args, &arg)?;
+                quirks = Quirks::from_name(&value).ok_or_else(|| {
+                    format!(
+                        "unknown quirks profile '{}', expected one of: {}",
+                        value,
+                        Quirks::NAMES.join(", ")
+                    )
+                })?;
+            }
+            "--seed" => {
+                let value = value_of(&mut args, &arg)?;
+                seed = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid value '{}' for --seed", value))?,
+                );
+            }
+            "--decode-cache" => decode_cache = true,
+            "--debug" => debug = true,
+            "--trace" => trace = Some(PathBuf::from(value_of(&mut args, &arg)?)),
+            "--trace-range" => {
+                let value = value_of(&mut args, &arg)?;
+                trace_ranges.push(
+                    parse_range(&value)
+                        .ok_or_else(|| format!("invalid value '{}' for --trace-range", value))?,
+                );
+            }
+            "--headless" => headless = true,
+            "--frames" => {
+                let value = value_of(&mut args, &arg)?;
+                frames = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid value '{}' for --frames", value))?,
+                );
+            }
+            "--input" => input = Some(PathBuf::from(value_of(&mut args, &arg)?)),
+            "--save-movie" => save_movie = Some(PathBuf::from(value_of(&mut args, &arg)?)),
+            "--play" => play = Some(PathBuf::from(value_of(&mut args, &arg)?)),
+            "--dump" => dump = Some(PathBuf::from(value_of(&mut args, &arg)?)),
+            "--record" => record = Some(PathBuf::from(value_of(&mut args, &arg)?)),
+            "--capture-scale" => {
+                let value = value_of(&mut args, &arg)?;
+                capture_scale = match value.parse::<usize>() {
+                    Ok(scale) if (1..=64).contains(&scale) => scale,
+                    _ => return Err(format!("invalid value '{}' for --capture-scale", value)),
+                };
+            }
+            "--mute" => mute = true,
+            "--wav" => wav = Some(PathBuf::from(value_of(&mut args, &arg)?)),
+            "--tone" => {
+                let value = value_of(&mut args, &arg)?;
+                beeper.frequency = match value.parse::<f32>() {
+                    Ok(frequency) if frequency > 0.0 => frequency,
+                    _ => return Err(format!("invalid value '{}' for --tone", value)),
+                };
+            }
+            "--volume" => {
+                let value = value_of(&mut args, &arg)?;
+                beeper.volume = match value.parse::<f32>() {
+                    Ok(volume) if (0.0..=1.0).contains(&volume) => volume,
+                    _ => return Err(format!("invalid value '{}' for --volume", value)),
+                };
+            }
+            "--waveform" => {
+                let value = value_of(&mut args, &arg)?;
+                beeper.waveform = Waveform::from_name(&value).ok_or_else(|| {
+                    format!(
+                        "unknown waveform '{}', expected one of: {}",
+                        value,
+                        Waveform::NAMES.join(", ")
+                    )
+                })?;
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+            _ => {
+                if rom.is_some() {
+                    return Err(format!("unexpected argument '{}'", arg));
+                }
+                rom = Some(PathBuf::from(arg));
+            }
+        }
+    }
+
+    let rom = rom.ok_or_else(|| String::from("no ROM given"))?;
+    if play.is_some() && input.is_some() {
+        return Err(String::from("--play and --input cannot be used together"));
+    }
+
+    Ok(Command::Run(Box::new(Options {
+        rom,
+        ips,
+        scale,
+        paused,
+        theme,
+        config,
+        quirks,
+        seed,
+        decode_cache,
+        debug,
+        trace,
+        trace_ranges,
+        headless,
+        frames,
+        input,
+        save_movie,
+        play,
+        dump,
+        record,
+        capture_scale,
+        mute,
+        wav,
+        beeper,
+    })))
+}
+
+/// Takes the value following an option
+fn value_of<I: Iterator<Item = String>>(args: &mut I, option: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("missing value for {}", option))
+}
+
+/// Parses an inclusive address range like `0x200-0x2ff`
+fn parse_range(text: &str) -> Option<(u16, u16)> {
+    let (start, end) = text.split_once('-')?;
+    let (start, end) = (parse_addr(start)?, parse_addr(end)?);
+
+    if start <= end {
+        Some((start, end))
+    } else {
+        None
+    }
+}
+
+/// Parses an address, in hex with a `0x` prefix or decimal
+fn parse_addr(text: &str) -> Option<u16> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
@@ -0,0 +1,214 @@
+use std::{fs, path::Path};
+
+use chip8_core::theme::{self, Palette, Theme};
+use winit::event::VirtualKeyCode;
+
+use crate::keymap::{self, Hotkey};
+
+/// Settings read from the `--config` file.
+///
+/// The file has `key = value` lines, grouped under `[section]` headers. Empty lines
+/// and lines starting with `#` are ignored. For example:
+///
+/// ```text
+/// theme = mine
+///
+/// [theme mine]
+/// background = #101820
+/// foreground = #F2AA4C
+/// ```
+///
+/// Themes need a background and foreground, `plane2` and `both` colours for XO-CHIP
+/// are derived from them unless given.
+///
+/// Keys are bound to CHIP-8 keys under `[keys]`, or `[keys <ROM file name>]` for a single
+/// ROM, and to emulator controls under `[hotkeys]`. Several keys are separated by commas,
+/// nothing after `=` leaves the key unbound:
+///
+/// ```text
+/// [keys pong.ch8]
+/// 1 = Up
+/// 4 = Down, S
+///
+/// [hotkeys]
+/// pause = P
+/// ```
+///
+/// A key bound to both a CHIP-8 key and a hotkey is an error, see `KeyMap::new`.
+#[derive(Debug, Default)]
+pub struct Config {
+    /// Theme to start with
+    pub theme: Option<String>,
+    /// Themes defined in the file
+    pub themes: Vec<Theme>,
+    /// CHIP-8 keypad bindings, in the order given
+    pub keys: Vec<KeyBinding>,
+    /// Hotkey bindings, in the order given
+    pub hotkeys: Vec<(Hotkey, Vec<VirtualKeyCode>)>,
+}
+
+/// Keyboard keys pressing a CHIP-8 key, replacing its default ones
+#[derive(Debug)]
+pub struct KeyBinding {
+    /// File name of the ROM this applies to, all ROMs if not set
+    pub rom: Option<String>,
+    /// CHIP-8 key, 0x0-0xF
+    pub code: u8,
+    pub keys: Vec<VirtualKeyCode>,
+}
+
+impl Config {
+    /// Reads config file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("could not read '{}': {}", path.display(), err))?;
+
+        Self::parse(&text).map_err(|err| format!("{} in '{}'", err, path.display()))
+    }
+
+    /// Parses contents of a config file
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = Config::default();
+        let mut section = Section::Top;
+
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = |msg: String| format!("{} on line {}", msg, idx + 1);
+
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header
+                    .strip_suffix(']')
+                    .ok_or_else(|| err(format!("unterminated section '{}'", line)))?;
+                if let Section::Theme(start, colours) = &section {
+                    config.finish_theme(*start, colours)?;
+                }
+                section = config.start_section(header.trim(), idx + 1).map_err(err)?;
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| err(format!("expected 'key = value', found '{}'", line)))?;
+            let (key, value) = (key.trim(), value.trim());
+
+            match section {
+                Section::Top => match key {
+                    "theme" => config.theme = Some(value.to_string()),
+                    _ => return Err(err(format!("unknown setting '{}'", key))),
+                },
+                Section::Theme(_, ref mut colours) => {
+                    let slot = match key {
+                        "background" => 0,
+                        "foreground" => 1,
+                        "plane2" => 2,
+                        "both" => 3,
+                        _ => return Err(err(format!("unknown theme colour '{}'", key))),
+                    };
+                    colours[slot] = Some(
+                        theme::parse_colour(value)
+                            .ok_or_else(|| err(format!("invalid colour '{}'", value)))?,
+                    );
+                }
+                Section::Keys(ref rom) => {
+                    let code = match u8::from_str_radix(key, 16) {
+                        Ok(code) if key.len() == 1 => code,
+                        _ => return Err(err(format!("invalid CHIP-8 key '{}'", key))),
+                    };
+                    config.keys.push(KeyBinding {
+                        rom: rom.clone(),
+                        code,
+                        keys: parse_keys(value).map_err(err)?,
+                    });
+                }
+                Section::Hotkeys => {
+                    let hotkey = Hotkey::from_name(key)
+                        .ok_or_else(|| err(format!("unknown hotkey '{}'", key)))?;
+                    config
+                        .hotkeys
+                        .push((hotkey, parse_keys(value).map_err(err)?));
+                }
+            }
+        }
+
+        if let Section::Theme(start, colours) = &section {
+            config.finish_theme(*start, colours)?;
+        }
+
+        Ok(config)
+    }
+
+    /// Enters section with given header, found on given line
+    fn start_section(&mut self, header: &str, line: usize) -> Result<Section, String> {
+        let mut words = header.split_whitespace();
+
+        match (words.next(), words.next(), words.next()) {
+            (Some("theme"), Some(name), None) => {
+                self.themes.retain(|theme| theme.name != name);
+                self.themes.push(Theme::new(name, [[0; 3]; 4]));
+                Ok(Section::Theme(line, [None; 4]))
+            }
+            (Some("keys"), None, None) => Ok(Section::Keys(None)),
+            // ROM file names may contain spaces
+            (Some("keys"), Some(_), _) => Ok(Section::Keys(Some(
+                header["keys".len()..].trim().to_string(),
+            ))),
+            (Some("hotkeys"), None, None) => Ok(Section::Hotkeys),
+            _ => Err(format!("unknown section '[{}]'", header)),
+        }
+    }
+
+    /// Sets colours of the theme just defined from given line, deriving the ones left out
+    fn finish_theme(&mut self, line: usize, colours: &[Option<[u8; 3]>; 4]) -> Result<(), String> {
+        let theme = self.themes.last_mut().unwrap();
+
+        let (background, foreground) = match (colours[0], colours[1]) {
+            (Some(background), Some(foreground)) => (background, foreground),
+            _ => {
+                return Err(format!(
+                    "theme '{}' needs a background and foreground on line {}",
+                    theme.name, line
+                ))
+            }
+        };
+
+        let derived = Theme::from_colours(&theme.name, background, foreground).palette;
+        let palette: Palette = [
+            background,
+            foreground,
+            colours[2].unwrap_or(derived[2]),
+            colours[3].unwrap_or(derived[3]),
+        ];
+        theme.palette = palette;
+        Ok(())
+    }
+}
+
+/// Part of the config file being parsed
+enum Section {
+    /// Before any section header
+    Top,
+    /// `[theme NAME]` on given line, colours given so far
+    Theme(usize, [Option<[u8; 3]>; 4]),
+    /// `[keys]`, or `[keys ROM]` for given ROM
+    Keys(Option<String>),
+    /// `[hotkeys]`
+    Hotkeys,
+}
+
+/// Parses comma separated key names, nothing meaning no keys
+fn parse_keys(value: &str) -> Result<Vec<VirtualKeyCode>, String> {
+    if value.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    value
+        .split(',')
+        .map(|name| {
+            let name = name.trim();
+            keymap::key_from_name(name).ok_or_else(|| format!("unknown key '{}'", name))
+        })
+        .collect()
+}
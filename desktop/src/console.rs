@@ -0,0 +1,121 @@
+use std::{
+    io::{self, BufRead, Write},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use chip8_core::{
+    debugger::Debugger,
+    emu::{Emulator, StepOutcome},
+    error::EmulatorError,
+};
+
+/// Prompt printed whenever the debugger waits for a command
+const PROMPT: &str = "(chip8) ";
+
+/// Debugger driven from the terminal.
+///
+/// Lines are read from stdin on a separate thread, so the window keeps drawing and
+/// handling events while the user types.
+pub struct Console {
+    pub debugger: Debugger,
+    lines: Receiver<String>,
+}
+
+impl Console {
+    /// Starts reading commands, with execution stopped before the first instruction
+    pub fn new(emu: &Emulator) -> Self {
+        let (sender, lines) = mpsc::channel();
+
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut debugger = Debugger::new();
+        debugger.stop();
+
+        let console = Console { debugger, lines };
+        console.prompt(&console.debugger.status(emu));
+        console
+    }
+
+    /// Runs commands typed so far, while stopped.
+    ///
+    /// Lines typed while running are kept for wherever execution stops next. With `block`
+    /// set, keeps waiting for commands until one resumes execution. Returns false once the
+    /// user quits, or stdin is closed while waiting.
+    pub fn poll(&mut self, emu: &mut Emulator, block: bool) -> bool {
+        while self.debugger.stopped {
+            let line = if block {
+                match self.lines.recv() {
+                    Ok(line) => line,
+                    Err(_) => return false,
+                }
+            } else {
+                match self.lines.try_recv() {
+                    Ok(line) => line,
+                    Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => return true,
+                }
+            };
+
+            let command = line.trim();
+            if command == "q" || command == "quit" {
+                return false;
+            }
+
+            match self.debugger.command(emu, command) {
+                Ok(output) => {
+                    print!("{}", output);
+                    if !output.is_empty() && !output.ends_with('\n') {
+                        println!();
+                    }
+                }
+                Err(err) => eprintln!("error: {}", err),
+            }
+
+            if self.debugger.stopped {
+                self.prompt("");
+            }
+        }
+
+        true
+    }
+
+    /// Stops execution wherever it is, e.g. on a hotkey
+    pub fn interrupt(&mut self, emu: &Emulator) {
+        if !self.debugger.stopped {
+            self.debugger.stop();
+            self.prompt(&self.debugger.status(emu));
+        }
+    }
+
+    /// Runs the Emulator under the debugger, reporting where it stops
+    pub fn run_for(
+        &mut self,
+        emu: &mut Emulator,
+        duration: Duration,
+    ) -> Result<StepOutcome, EmulatorError> {
+        let stopped = self.debugger.stopped;
+        let outcome = self.debugger.run_for(emu, duration);
+
+        if !stopped && self.debugger.stopped {
+            self.prompt(&self.debugger.status(emu));
+        }
+
+        outcome
+    }
+
+    /// Prints a message, if any, followed by the prompt
+    fn prompt(&self, message: &str) {
+        if !message.is_empty() {
+            println!("{}", message);
+        }
+        print!("{}", PROMPT);
+        io::stdout().flush().ok();
+    }
+}
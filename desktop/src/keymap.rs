@@ -0,0 +1,280 @@
+use std::{collections::HashMap, path::Path};
+
+use winit::event::VirtualKeyCode;
+
+use crate::config::Config;
+
+/// Emulator controls, bound to keys apart from the CHIP-8 keypad
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Hotkey {
+    Pause,
+    Reset,
+    /// Pause and run a single instruction
+    Step,
+    SpeedUp,
+    SpeedDown,
+    /// Rewind while held
+    Rewind,
+    QuickSave,
+    QuickLoad,
+    NextTheme,
+    /// Stop in the debugger, with `--debug`
+    Break,
+    Screenshot,
+    Record,
+}
+
+impl Hotkey {
+    /// Hotkeys with their names in the config file and default keys
+    pub const ALL: [(Hotkey, &'static str, VirtualKeyCode); 12] = [
+        (Hotkey::Pause, "pause", VirtualKeyCode::Space),
+        (Hotkey::Reset, "reset", VirtualKeyCode::F2),
+        (Hotkey::Step, "step", VirtualKeyCode::F10),
+        (Hotkey::SpeedUp, "speed-up", VirtualKeyCode::Equals),
+        (Hotkey::SpeedDown, "speed-down", VirtualKeyCode::Minus),
+        (Hotkey::Rewind, "rewind", VirtualKeyCode::Back),
+        (Hotkey::QuickSave, "save", VirtualKeyCode::F5),
+        (Hotkey::QuickLoad, "load", VirtualKeyCode::F9),
+        (Hotkey::NextTheme, "theme", VirtualKeyCode::F6),
+        (Hotkey::Break, "break", VirtualKeyCode::F8),
+        (Hotkey::Screenshot, "screenshot", VirtualKeyCode::F12),
+        (Hotkey::Record, "record", VirtualKeyCode::F11),
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|(_, hotkey_name, _)| *hotkey_name == name)
+            .map(|(hotkey, _, _)| *hotkey)
+    }
+
+    /// Name in the config file
+    pub fn name(self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|(hotkey, _, _)| *hotkey == self)
+            .map(|(_, name, _)| *name)
+            .unwrap()
+    }
+}
+
+/// Conventional layout of the COSMAC VIP keypad on the left of a QWERTY keyboard:
+///
+/// ```text
+/// 1 2 3 C      1 2 3 4
+/// 4 5 6 D      Q W E R
+/// 7 8 9 E  ->  A S D F
+/// A 0 B F      Z X C V
+/// ```
+const DEFAULT_KEYPAD: [(u8, VirtualKeyCode); 16] = [
+    (0x1, VirtualKeyCode::Key1),
+    (0x2, VirtualKeyCode::Key2),
+    (0x3, VirtualKeyCode::Key3),
+    (0xC, VirtualKeyCode::Key4),
+    (0x4, VirtualKeyCode::Q),
+    (0x5, VirtualKeyCode::W),
+    (0x6, VirtualKeyCode::E),
+    (0xD, VirtualKeyCode::R),
+    (0x7, VirtualKeyCode::A),
+    (0x8, VirtualKeyCode::S),
+    (0x9, VirtualKeyCode::D),
+    (0xE, VirtualKeyCode::F),
+    (0xA, VirtualKeyCode::Z),
+    (0x0, VirtualKeyCode::X),
+    (0xB, VirtualKeyCode::C),
+    (0xF, VirtualKeyCode::V),
+];
+
+/// Which keyboard keys press which CHIP-8 keys, and which ones are hotkeys
+pub struct KeyMap {
+    keypad: HashMap<VirtualKeyCode, u8>,
+    hotkeys: HashMap<VirtualKeyCode, Hotkey>,
+}
+
+impl KeyMap {
+    /// Default bindings, overridden by the config file's `[keys]` and `[hotkeys]`, then by
+    /// its `[keys <ROM>]` for the ROM being run.
+    ///
+    /// Fails if a key ends up bound both to a CHIP-8 key and a hotkey.
+    pub fn new(config: &Config, rom: &Path) -> Result<Self, String> {
+        let mut keymap = KeyMap {
+            keypad: DEFAULT_KEYPAD
+                .iter()
+                .map(|&(code, key)| (key, code))
+                .collect(),
+            hotkeys: Hotkey::ALL
+                .iter()
+                .map(|&(hotkey, _, key)| (key, hotkey))
+                .collect(),
+        };
+
+        for (hotkey, keys) in &config.hotkeys {
+            keymap.hotkeys.retain(|_, bound| bound != hotkey);
+            for key in keys {
+                keymap.hotkeys.insert(*key, *hotkey);
+            }
+        }
+
+        let rom_name = rom.file_name().and_then(|name| name.to_str());
+        let global = config.keys.iter().filter(|binding| binding.rom.is_none());
+        let per_rom = config
+            .keys
+            .iter()
+            .filter(|binding| binding.rom.is_some() && binding.rom.as_deref() == rom_name);
+
+        for binding in global.chain(per_rom) {
+            keymap.keypad.retain(|_, code| *code != binding.code);
+            for key in &binding.keys {
+                keymap.keypad.insert(*key, binding.code);
+            }
+        }
+
+        let mut conflicts: Vec<String> = keymap
+            .keypad
+            .iter()
+            .filter_map(|(key, code)| {
+                let hotkey = keymap.hotkeys.get(key)?;
+                Some(format!(
+                    "key {:?} is bound to both CHIP-8 key {:X} and hotkey '{}'",
+                    key,
+                    code,
+                    hotkey.name()
+                ))
+            })
+            .collect();
+        conflicts.sort();
+
+        match conflicts.into_iter().next() {
+            Some(conflict) => Err(conflict),
+            None => Ok(keymap),
+        }
+    }
+
+    /// CHIP-8 key pressed by given keyboard key
+    pub fn keypad(&self, key: VirtualKeyCode) -> Option<u8> {
+        self.keypad.get(&key).copied()
+    }
+
+    /// Hotkey bound to given keyboard key
+    pub fn hotkey(&self, key: VirtualKeyCode) -> Option<Hotkey> {
+        self.hotkeys.get(&key).copied()
+    }
+
+    /// First key bound to given hotkey, for hints like "press F11 to stop"
+    pub fn hotkey_name(&self, hotkey: Hotkey) -> String {
+        let mut keys: Vec<String> = self
+            .hotkeys
+            .iter()
+            .filter(|(_, bound)| **bound == hotkey)
+            .map(|(key, _)| format!("{:?}", key))
+            .collect();
+        keys.sort();
+
+        keys.into_iter()
+            .next()
+            .unwrap_or_else(|| String::from("(unbound)"))
+    }
+}
+
+/// Parses a keyboard key name as used in the config file, e.g. `Q`, `5`, `F1` or `Space`.
+///
+/// Names follow winit's `VirtualKeyCode`, ignoring case, with digits written plainly.
+pub fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+    use VirtualKeyCode::*;
+
+    let key = match name.to_ascii_lowercase().as_str() {
+        "0" => Key0,
+        "1" => Key1,
+        "2" => Key2,
+        "3" => Key3,
+        "4" => Key4,
+        "5" => Key5,
+        "6" => Key6,
+        "7" => Key7,
+        "8" => Key8,
+        "9" => Key9,
+        "a" => A,
+        "b" => B,
+        "c" => C,
+        "d" => D,
+        "e" => E,
+        "f" => F,
+        "g" => G,
+        "h" => H,
+        "i" => I,
+        "j" => J,
+        "k" => K,
+        "l" => L,
+        "m" => M,
+        "n" => N,
+        "o" => O,
+        "p" => P,
+        "q" => Q,
+        "r" => R,
+        "s" => S,
+        "t" => T,
+        "u" => U,
+        "v" => V,
+        "w" => W,
+        "x" => X,
+        "y" => Y,
+        "z" => Z,
+        "f1" => F1,
+        "f2" => F2,
+        "f3" => F3,
+        "f4" => F4,
+        "f5" => F5,
+        "f6" => F6,
+        "f7" => F7,
+        "f8" => F8,
+        "f9" => F9,
+        "f10" => F10,
+        "f11" => F11,
+        "f12" => F12,
+        "numpad0" => Numpad0,
+        "numpad1" => Numpad1,
+        "numpad2" => Numpad2,
+        "numpad3" => Numpad3,
+        "numpad4" => Numpad4,
+        "numpad5" => Numpad5,
+        "numpad6" => Numpad6,
+        "numpad7" => Numpad7,
+        "numpad8" => Numpad8,
+        "numpad9" => Numpad9,
+        "up" => Up,
+        "down" => Down,
+        "left" => Left,
+        "right" => Right,
+        "space" => Space,
+        "back" | "backspace" => Back,
+        "return" | "enter" => Return,
+        "tab" => Tab,
+        "escape" => Escape,
+        "insert" => Insert,
+        "delete" => Delete,
+        "home" => Home,
+        "end" => End,
+        "pageup" => PageUp,
+        "pagedown" => PageDown,
+        "minus" => Minus,
+        "equals" => Equals,
+        "comma" => Comma,
+        "period" => Period,
+        "slash" => Slash,
+        "backslash" => Backslash,
+        "semicolon" => Semicolon,
+        "apostrophe" => Apostrophe,
+        "grave" => Grave,
+        "lbracket" => LBracket,
+        "rbracket" => RBracket,
+        "lshift" => LShift,
+        "rshift" => RShift,
+        "lcontrol" => LControl,
+        "rcontrol" => RControl,
+        "lalt" => LAlt,
+        "ralt" => RAlt,
+        _ => return None,
+    };
+
+    Some(key)
+}
@@ -0,0 +1,6 @@
+//! Parts of the desktop frontend that need no window or sound device: command line, config
+//! file and key bindings.
+
+pub mod cli;
+pub mod config;
+pub mod keymap;
@@ -0,0 +1,826 @@
+mod console;
+#[cfg(feature = "sound")]
+mod sound;
+
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    process, thread,
+    time::{Duration, Instant},
+};
+
+use winit::{
+    dpi::{LogicalSize, Size},
+    event::{ElementState, Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
+
+use pixels::{Pixels, SurfaceTexture};
+
+use chip8_core::{
+    audio::{AudioSink, NullSink, WavSink},
+    emu::{self, StepOutcome, COLS, ROWS},
+    error::EmulatorError,
+    gif::GifRecorder,
+    headless::{self, InputScript},
+    image,
+    movie::{Movie, Playback},
+    rewind::Rewind,
+    theme::{Palette, Theme},
+    trace::Tracer,
+};
+
+use chip8::{
+    cli::{self, Command, Options},
+    config::Config,
+    keymap::{Hotkey, KeyMap},
+};
+
+use crate::console::Console;
+
+/// Number of frames kept for rewinding, 30 seconds at 60 fps
+const REWIND_FRAMES: usize = 60 * 30;
+/// Time between emulator updates and redraws
+const FRAME_INTERVAL: Duration = Duration::from_micros(1_000_000 / 60);
+/// Longest stretch of time caught up at once, anything beyond is skipped (e.g. after window was dragged)
+const MAX_CATCH_UP: Duration = Duration::from_millis(250);
+
+/// Sample rate of audio written to files or discarded
+const SAMPLE_RATE: u32 = 44_100;
+
+/// Highest speed reachable with the speed up hotkey
+const MAX_IPS: u32 = 1_000_000;
+
+/// Creates emulator set up by the options, with the ROM loaded
+fn new_emulator(options: &Options) -> Result<emu::Emulator, String> {
+    let mut emu = match options.seed {
+        Some(seed) => emu::Emulator::with_seed(seed),
+        None => emu::Emulator::new(),
+    };
+    emu.ips = options.ips;
+    emu.quirks = options.quirks;
+    emu.set_decode_cache(options.decode_cache);
+    emu.load_sprites();
+
+    load_rom(&mut emu, &options.rom)?;
+    Ok(emu)
+}
+
+/// Reads ROM from disk and loads it into the Emulator
+fn load_rom(emu: &mut emu::Emulator, path: &Path) -> Result<(), String> {
+    let rom = fs::read(path)
+        .map_err(|err| format!("could not read ROM '{}': {}", path.display(), err))?;
+
+    emu.load_rom(&rom)
+        .map_err(|err| format!("could not load ROM '{}': {}", path.display(), err))
+}
+
+/// Path of the quick save slot for given ROM, next to the ROM itself
+fn state_path(rom: &Path) -> PathBuf {
+    let mut path = rom.as_os_str().to_owned();
+    path.push(".state");
+    PathBuf::from(path)
+}
+
+/// First unused `<rom>.<n>.<ext>` path, for screenshots and recordings next to the ROM
+fn capture_path(rom: &Path, ext: &str) -> PathBuf {
+    (1..)
+        .map(|n| {
+            let mut path = rom.as_os_str().to_owned();
+            path.push(format!(".{}.{}", n, ext));
+            PathBuf::from(path)
+        })
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+/// Writes current display as a PNG image
+fn screenshot(
+    emu: &emu::Emulator,
+    path: &Path,
+    palette: &Palette,
+    scale: usize,
+) -> Result<(), String> {
+    fs::write(path, image::png(emu, palette, scale))
+        .map_err(|err| format!("could not write '{}': {}", path.display(), err))
+}
+
+/// Writes out a finished recording
+fn save_recording(recorder: GifRecorder, path: &Path) -> Result<(), String> {
+    fs::write(path, recorder.finish())
+        .map_err(|err| format!("could not write '{}': {}", path.display(), err))
+}
+
+/// Writes current state into the quick save slot
+fn quick_save(emu: &emu::Emulator, path: &Path) -> Result<(), String> {
+    fs::write(path, emu.save_state())
+        .map_err(|err| format!("could not write '{}': {}", path.display(), err))
+}
+
+/// Restores state from the quick save slot
+fn quick_load(emu: &mut emu::Emulator, path: &Path) -> Result<(), String> {
+    let data =
+        fs::read(path).map_err(|err| format!("could not read '{}': {}", path.display(), err))?;
+
+    emu.load_state(&data)
+        .map_err(|err| format!("could not load '{}': {}", path.display(), err))
+}
+
+/// Reads movie to replay
+fn load_movie(path: &Path) -> Result<Movie, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("could not read '{}': {}", path.display(), err))?;
+
+    Movie::parse(&text).map_err(|err| format!("{} in '{}'", err, path.display()))
+}
+
+/// Writes key presses recorded so far
+fn save_movie(emu: &emu::Emulator, path: &Path) -> Result<(), String> {
+    let movie = match &emu.movie {
+        Some(movie) => movie,
+        None => return Ok(()),
+    };
+
+    fs::write(path, movie.to_text())
+        .map_err(|err| format!("could not write '{}': {}", path.display(), err))
+}
+
+/// Runs the emulator for given amount of emulated time, through the debugger and the movie
+/// being replayed if there are any
+fn run_for(
+    emu: &mut emu::Emulator,
+    duration: Duration,
+    console: &mut Option<Console>,
+    playback: &mut Option<Playback>,
+) -> Result<StepOutcome, EmulatorError> {
+    let mut run = |emu: &mut emu::Emulator, duration| match console {
+        Some(console) => console.run_for(emu, duration),
+        None => emu.run_for(duration),
+    };
+
+    let outcome = match playback {
+        Some(movie) => movie.run_for(emu, duration, run),
+        None => run(emu, duration),
+    };
+
+    if playback.as_ref().is_some_and(Playback::is_finished) {
+        eprintln!("movie finished at frame {}", emu.clock.timer_ticks);
+        *playback = None;
+    }
+
+    outcome
+}
+
+/// Themes to switch between, built-in ones followed by those from the config file, and
+/// the index of the one to start with
+fn load_themes(options: &Options, config: &Config) -> Result<(Vec<Theme>, usize), String> {
+    let mut themes = Theme::builtin();
+    for theme in &config.themes {
+        match themes.iter_mut().find(|builtin| builtin.name == theme.name) {
+            Some(builtin) => *builtin = theme.clone(),
+            None => themes.push(theme.clone()),
+        }
+    }
+
+    let current = match options.theme.as_ref().or(config.theme.as_ref()) {
+        Some(name) => themes
+            .iter()
+            .position(|theme| &theme.name == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = themes.iter().map(|theme| theme.name.as_str()).collect();
+                format!(
+                    "unknown theme '{}', expected one of: {}",
+                    name,
+                    names.join(", ")
+                )
+            })?,
+        None => 0,
+    };
+
+    Ok((themes, current))
+}
+
+/// Picks where sound goes: a WAV file, the sound card, or nowhere
+fn open_audio(options: &Options) -> Result<Box<dyn AudioSink>, String> {
+    if let Some(path) = &options.wav {
+        let sink = WavSink::create(path, SAMPLE_RATE)
+            .map_err(|err| format!("could not create '{}': {}", path.display(), err))?;
+        return Ok(Box::new(sink));
+    }
+
+    if options.mute || options.headless {
+        return Ok(Box::new(NullSink::new(SAMPLE_RATE)));
+    }
+
+    #[cfg(feature = "sound")]
+    match sound::DeviceSink::open() {
+        Ok(sink) => return Ok(Box::new(sink)),
+        Err(err) => eprintln!(
+            "warning: sound disabled, could not open audio device: {}",
+            err
+        ),
+    }
+
+    Ok(Box::new(NullSink::new(SAMPLE_RATE)))
+}
+
+/// Creates the `--trace` file and starts tracing, if asked for
+fn open_trace(
+    emu: &mut emu::Emulator,
+    options: &Options,
+) -> Result<Option<BufWriter<File>>, String> {
+    let path = match &options.trace {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    let file = File::create(path)
+        .map_err(|err| format!("could not create '{}': {}", path.display(), err))?;
+
+    let mut tracer = Tracer::new();
+    tracer.ranges = options.trace_ranges.clone();
+    emu.tracer = Some(tracer);
+
+    Ok(Some(BufWriter::new(file)))
+}
+
+/// Writes out instructions traced since the last call
+fn write_trace(emu: &mut emu::Emulator, out: &mut Option<BufWriter<File>>) {
+    let (tracer, file) = match (&mut emu.tracer, out.as_mut()) {
+        (Some(tracer), Some(file)) => (tracer, file),
+        _ => return,
+    };
+
+    let result = tracer
+        .drain()
+        .try_for_each(|entry| writeln!(file, "{}", entry))
+        .and_then(|()| file.flush());
+
+    if let Err(err) = result {
+        eprintln!("error: could not write trace: {}", err);
+        *out = None;
+        emu.tracer = None;
+    }
+}
+
+/// Writes final display where `--dump` asks for, as text on stdout by default
+fn dump_display(
+    emu: &emu::Emulator,
+    path: Option<&Path>,
+    palette: &Palette,
+    scale: usize,
+) -> Result<(), String> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            print!("{}", image::ascii(emu));
+            return Ok(());
+        }
+    };
+
+    let data = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("png") => image::png(emu, palette, scale),
+        Some("pbm") => image::pbm(emu),
+        _ => image::ascii(emu).into_bytes(),
+    };
+
+    fs::write(path, data).map_err(|err| format!("could not write '{}': {}", path.display(), err))
+}
+
+/// Runs emulator without a window, then prints its final state.
+///
+/// Runs in real time until the program exits, or as fast as possible for `--frames` frames.
+/// Under `--debug`, also stops once the debugger is quit or stdin closes.
+/// Exits with 1 if the emulator fails.
+fn run_headless(
+    mut emu: emu::Emulator,
+    options: &Options,
+    mut playback: Option<Playback>,
+    mut audio: Box<dyn AudioSink>,
+    mut console: Option<Console>,
+    mut trace: Option<BufWriter<File>>,
+    palette: &Palette,
+) -> ! {
+    let mut beeper = options.beeper.clone();
+    let mut script = match &options.input {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|err| format!("could not read '{}': {}", path.display(), err))
+            .and_then(|text| {
+                InputScript::parse(&text).map_err(|err| format!("{} in '{}'", err, path.display()))
+            }),
+        None => Ok(InputScript::default()),
+    }
+    .unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    });
+
+    let mut recorder = options
+        .record
+        .as_ref()
+        .map(|_| GifRecorder::new(palette, options.capture_scale));
+    let mut last_update = Instant::now();
+
+    let result = loop {
+        if let Some(frames) = options.frames {
+            if emu.clock.timer_ticks >= frames {
+                break Ok(());
+            }
+        }
+
+        script.apply(&mut emu);
+
+        if let Some(console) = &mut console {
+            let stopped = console.debugger.stopped;
+            if !console.poll(&mut emu, true) {
+                break Ok(());
+            }
+            // Time spent waiting for commands is not emulated
+            if stopped {
+                last_update = Instant::now();
+            }
+        }
+
+        let (elapsed, outcome) = if options.frames.is_some() {
+            // Exactly the time up to the next timer update, same as `cycle`
+            let frame = Duration::from_nanos(emu.clock.next_timer() - emu.clock.now);
+            (
+                FRAME_INTERVAL,
+                run_for(&mut emu, frame, &mut console, &mut playback),
+            )
+        } else {
+            thread::sleep(FRAME_INTERVAL);
+
+            let now = Instant::now();
+            let elapsed = (now - last_update).min(MAX_CATCH_UP);
+            last_update = now;
+
+            (
+                elapsed,
+                run_for(&mut emu, elapsed, &mut console, &mut playback),
+            )
+        };
+
+        write_trace(&mut emu, &mut trace);
+        if let Some(recorder) = &mut recorder {
+            recorder.push(&emu, elapsed);
+        }
+
+        match outcome {
+            Ok(StepOutcome::Exited) => break Ok(()),
+            Ok(_) => {
+                if let Err(err) = beeper.play(&emu, elapsed, audio.as_mut()) {
+                    eprintln!("error: could not write audio: {}", err);
+                }
+            }
+            Err(err) => break Err(err),
+        }
+    };
+
+    // Exiting skips destructors, make sure recorded audio is complete
+    if let Err(err) = audio.flush() {
+        eprintln!("error: could not write audio: {}", err);
+    }
+
+    let mut code = 0;
+    if let Some(path) = &options.save_movie {
+        if let Err(err) = save_movie(&emu, path) {
+            eprintln!("error: {}", err);
+            code = 1;
+        }
+    }
+    if let (Some(recorder), Some(path)) = (recorder, &options.record) {
+        if let Err(err) = save_recording(recorder, path) {
+            eprintln!("error: {}", err);
+            code = 1;
+        }
+    }
+    if let Err(err) = dump_display(
+        &emu,
+        options.dump.as_deref(),
+        palette,
+        options.capture_scale,
+    ) {
+        eprintln!("error: {}", err);
+        code = 1;
+    }
+    print!("{}", headless::registers(&emu));
+
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        code = 1;
+    }
+    process::exit(code);
+}
+
+fn main() {
+    let mut options = match cli::parse(std::env::args().skip(1)) {
+        Ok(Command::Run(options)) => *options,
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, cli::USAGE);
+            process::exit(2);
+        }
+    };
+
+    // Movies are replayed with the settings they were recorded with
+    let movie = match options.play.as_deref().map(load_movie).transpose() {
+        Ok(movie) => movie,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    };
+    if let Some(movie) = &movie {
+        options.seed = Some(movie.seed);
+        options.ips = movie.ips;
+        options.quirks = movie.quirks;
+    }
+
+    let mut emu = match new_emulator(&options) {
+        Ok(emu) => emu,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    };
+
+    if let Some(movie) = &movie {
+        if let Err(err) = movie.check_rom(&emu) {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    }
+    let mut playback = movie.as_ref().map(Playback::new);
+    if options.save_movie.is_some() {
+        emu.movie = Some(Movie::new(&emu));
+    }
+
+    let mut audio = match open_audio(&options) {
+        Ok(audio) => audio,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    };
+
+    let mut trace = match open_trace(&mut emu, &options) {
+        Ok(trace) => trace,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    };
+
+    let config = match &options.config {
+        Some(path) => Config::load(path),
+        None => Ok(Config::default()),
+    };
+    let config = match config {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    };
+
+    let (themes, mut theme) = match load_themes(&options, &config) {
+        Ok(themes) => themes,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    };
+
+    let keymap = match KeyMap::new(&config, &options.rom) {
+        Ok(keymap) => keymap,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    };
+
+    let mut console = if options.debug {
+        Some(Console::new(&emu))
+    } else {
+        None
+    };
+
+    if options.headless {
+        run_headless(
+            emu,
+            &options,
+            playback,
+            audio,
+            console,
+            trace,
+            &themes[theme].palette,
+        );
+    }
+
+    let mut beeper = options.beeper.clone();
+    let scale = options.scale;
+    let width = COLS as f64 * scale;
+    let height = ROWS as f64 * scale;
+
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new()
+        .with_title("CHIP-8 Emulator")
+        .with_inner_size(Size::Logical(LogicalSize { width, height }))
+        .build(&event_loop)
+        .unwrap();
+
+    let window_size = window.inner_size();
+    let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
+    let mut pixels = Pixels::new(COLS as u32, ROWS as u32, surface_texture).unwrap();
+
+    // Paused by the user, unlike `emu.paused` which waits for a key press
+    let mut paused = options.paused;
+    // Set once the emulator fails, no further instructions are executed
+    let mut halted = false;
+    // Quick save slot, written with F5 and restored with F9
+    let state_path = state_path(&options.rom);
+    // Size of `pixels` buffer, follows SUPER-CHIP resolution changes
+    let mut buffer_size = (COLS, ROWS);
+    // Recent frames, stepped back through while Backspace is held
+    let mut rewind = Rewind::new(REWIND_FRAMES);
+    let mut rewinding = false;
+    rewind.push(&emu);
+    // GIF being recorded and where it goes, toggled with F11 or started by `--record`
+    let mut recording = options.record.clone().map(|path| {
+        (
+            GifRecorder::new(&themes[theme].palette, options.capture_scale),
+            path,
+        )
+    });
+    let capture_scale = options.capture_scale;
+    let rom_path = options.rom.clone();
+
+    let mut last_update = Instant::now();
+
+    event_loop.run(move |event, _, control_flow| {
+        // Wake up for the next frame even if there are no window events
+        *control_flow = ControlFlow::WaitUntil(last_update + FRAME_INTERVAL);
+
+        match event {
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                window_id,
+            } if window_id == window.id() => *control_flow = ControlFlow::Exit,
+            Event::LoopDestroyed => {
+                if let Err(err) = audio.flush() {
+                    eprintln!("error: could not write audio: {}", err);
+                }
+                if let Some((recorder, path)) = recording.take() {
+                    if let Err(err) = save_recording(recorder, &path) {
+                        eprintln!("error: {}", err);
+                    }
+                }
+                if let Some(path) = &options.save_movie {
+                    if let Err(err) = save_movie(&emu, path) {
+                        eprintln!("error: {}", err);
+                    }
+                }
+            }
+            Event::MainEventsCleared => {
+                let now = Instant::now();
+                let elapsed = now - last_update;
+
+                if elapsed < FRAME_INTERVAL {
+                    return;
+                }
+                last_update = now;
+
+                if let Some(console) = &mut console {
+                    if !console.poll(&mut emu, false) {
+                        *control_flow = ControlFlow::Exit;
+                        return;
+                    }
+                }
+
+                if rewinding {
+                    match rewind.rewind(&mut emu) {
+                        Ok(true) => {
+                            window.set_title("CHIP-8 Emulator");
+                            halted = false;
+                        }
+                        Ok(false) => {}
+                        Err(err) => eprintln!("error: could not rewind: {}", err),
+                    }
+                } else if !paused && !halted {
+                    let elapsed = elapsed.min(MAX_CATCH_UP);
+                    let outcome = run_for(&mut emu, elapsed, &mut console, &mut playback);
+                    write_trace(&mut emu, &mut trace);
+
+                    match outcome {
+                        Ok(StepOutcome::Exited) => {
+                            *control_flow = ControlFlow::Exit;
+                            return;
+                        }
+                        Ok(_) => {
+                            rewind.push(&emu);
+                            if let Err(err) = beeper.play(&emu, elapsed, audio.as_mut()) {
+                                eprintln!("error: could not write audio: {}", err);
+                            }
+                        }
+                        Err(err) => {
+                            eprintln!("error: {}", err);
+                            window.set_title(&format!("CHIP-8 Emulator - {}", err));
+                            halted = true;
+                        }
+                    }
+                }
+
+                if let Some((recorder, _)) = &mut recording {
+                    recorder.push(&emu, elapsed.min(MAX_CATCH_UP));
+                }
+
+                *control_flow = ControlFlow::WaitUntil(last_update + FRAME_INTERVAL);
+                window.request_redraw();
+            }
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input,
+                        device_id: _,
+                        is_synthetic: _,
+                    },
+                window_id,
+            } if window_id == window.id() => {
+                let key = match input.virtual_keycode {
+                    Some(key) => key,
+                    None => return,
+                };
+                let pressed = input.state == ElementState::Pressed;
+
+                let hotkey = match keymap.hotkey(key) {
+                    Some(hotkey) => hotkey,
+                    None => {
+                        // Keypad belongs to the movie being replayed
+                        if playback.is_some() {
+                            return;
+                        }
+                        if let Some(code) = keymap.keypad(key) {
+                            if pressed {
+                                emu.key_down(code);
+                            } else {
+                                emu.key_up(code);
+                            }
+                        }
+                        return;
+                    }
+                };
+
+                // Going back in time or changing speed would make movies impossible to replay
+                let in_movie = emu.movie.is_some() || playback.is_some();
+                let breaks_movie = matches!(
+                    hotkey,
+                    Hotkey::Rewind | Hotkey::QuickLoad | Hotkey::SpeedUp | Hotkey::SpeedDown
+                );
+                if in_movie && breaks_movie {
+                    if pressed {
+                        eprintln!("not available while recording or replaying a movie");
+                    }
+                    return;
+                }
+
+                if hotkey == Hotkey::Rewind {
+                    rewinding = pressed;
+                    return;
+                }
+                if !pressed {
+                    return;
+                }
+
+                match hotkey {
+                    Hotkey::Pause => paused = !paused,
+                    Hotkey::Reset => match new_emulator(&options) {
+                        Ok(mut new) => {
+                            new.watchpoints = std::mem::take(&mut emu.watchpoints);
+                            new.tracer = emu.tracer.take();
+                            // Movies start over along with the emulator
+                            if emu.movie.is_some() {
+                                new.movie = Some(Movie::new(&new));
+                            }
+                            playback = movie.as_ref().map(Playback::new);
+                            emu = new;
+                            rewind = Rewind::new(REWIND_FRAMES);
+                            rewind.push(&emu);
+                            window.set_title("CHIP-8 Emulator");
+                            halted = false;
+                            eprintln!("reset");
+                        }
+                        Err(err) => eprintln!("error: {}", err),
+                    },
+                    Hotkey::Step => {
+                        paused = true;
+                        if halted {
+                            return;
+                        }
+                        if let Some(playback) = &mut playback {
+                            playback.apply(&mut emu);
+                        }
+                        let outcome = emu.run_instruction();
+                        write_trace(&mut emu, &mut trace);
+
+                        match outcome {
+                            Ok(StepOutcome::Exited) => *control_flow = ControlFlow::Exit,
+                            Ok(_) => {
+                                rewind.push(&emu);
+                                eprintln!("{}", headless::registers(&emu).trim_end());
+                            }
+                            Err(err) => {
+                                eprintln!("error: {}", err);
+                                window.set_title(&format!("CHIP-8 Emulator - {}", err));
+                                halted = true;
+                            }
+                        }
+                        window.request_redraw();
+                    }
+                    Hotkey::SpeedUp | Hotkey::SpeedDown => {
+                        emu.ips = match hotkey {
+                            Hotkey::SpeedUp => emu.ips.saturating_mul(2).min(MAX_IPS),
+                            _ => (emu.ips / 2).max(1),
+                        };
+                        eprintln!("speed: {} instructions per second", emu.ips);
+                    }
+                    Hotkey::Rewind => {}
+                    Hotkey::Screenshot => {
+                        let path = capture_path(&rom_path, "png");
+                        match screenshot(&emu, &path, &themes[theme].palette, capture_scale) {
+                            Ok(()) => eprintln!("saved screenshot to '{}'", path.display()),
+                            Err(err) => eprintln!("error: {}", err),
+                        }
+                    }
+                    Hotkey::Record => match recording.take() {
+                        Some((recorder, path)) => {
+                            let frames = recorder.len();
+                            match save_recording(recorder, &path) {
+                                Ok(()) => {
+                                    eprintln!("saved {} frames to '{}'", frames, path.display())
+                                }
+                                Err(err) => eprintln!("error: {}", err),
+                            }
+                        }
+                        None => {
+                            let path = capture_path(&rom_path, "gif");
+                            eprintln!(
+                                "recording to '{}', press {} to stop",
+                                path.display(),
+                                keymap.hotkey_name(Hotkey::Record)
+                            );
+                            let recorder = GifRecorder::new(&themes[theme].palette, capture_scale);
+                            recording = Some((recorder, path));
+                        }
+                    },
+                    Hotkey::NextTheme => {
+                        theme = (theme + 1) % themes.len();
+                        eprintln!("theme: {}", themes[theme].name);
+                        window.request_redraw();
+                    }
+                    Hotkey::Break => {
+                        if let Some(console) = &mut console {
+                            console.interrupt(&emu);
+                        }
+                    }
+                    Hotkey::QuickSave => match quick_save(&emu, &state_path) {
+                        Ok(()) => eprintln!("saved state to '{}'", state_path.display()),
+                        Err(err) => eprintln!("error: {}", err),
+                    },
+                    Hotkey::QuickLoad => match quick_load(&mut emu, &state_path) {
+                        Ok(()) => {
+                            eprintln!("loaded state from '{}'", state_path.display());
+                            window.set_title("CHIP-8 Emulator");
+                            halted = false;
+                        }
+                        Err(err) => eprintln!("error: {}", err),
+                    },
+                }
+            }
+            Event::RedrawRequested(wid) if wid == window.id() => {
+                if buffer_size != (emu.width(), emu.height()) {
+                    buffer_size = (emu.width(), emu.height());
+                    pixels.resize_buffer(buffer_size.0 as u32, buffer_size.1 as u32);
+                }
+
+                let frame = pixels.get_frame();
+                for (idx, px) in emu.display.iter().enumerate() {
+                    let fi = idx * 4;
+                    let rgb = themes[theme].palette[*px as usize & 3];
+                    frame[fi..fi + 3].copy_from_slice(&rgb);
+                    frame[fi + 3] = 255;
+                }
+
+                if pixels.render().is_err() {
+                    *control_flow = ControlFlow::Exit;
+                }
+            }
+            _ => (),
+        }
+    });
+}
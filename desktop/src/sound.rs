@@ -0,0 +1,94 @@
+use std::{
+    collections::VecDeque,
+    io,
+    sync::{Arc, Mutex},
+};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+use chip8_core::audio::AudioSink;
+
+/// Longest stretch of audio buffered for the sound card, in seconds. Anything beyond is dropped,
+/// so that latency does not build up when the emulator runs ahead of playback.
+const MAX_BUFFERED: f32 = 0.1;
+
+/// Sink that plays audio on the default output device
+pub struct DeviceSink {
+    buffer: Arc<Mutex<VecDeque<f32>>>,
+    sample_rate: u32,
+    // Playback stops once the stream is dropped
+    _stream: cpal::Stream,
+}
+
+impl DeviceSink {
+    /// Opens default output device of the default host
+    pub fn open() -> Result<Self, String> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| String::from("no audio output device"))?;
+        let config = device
+            .default_output_config()
+            .map_err(|err| err.to_string())?;
+
+        let sample_rate = config.sample_rate().0;
+        let buffer = Arc::new(Mutex::new(VecDeque::new()));
+
+        let stream = match config.sample_format() {
+            cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config.into(), &buffer),
+            cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config.into(), &buffer),
+            cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config.into(), &buffer),
+        }?;
+        stream.play().map_err(|err| err.to_string())?;
+
+        Ok(Self {
+            buffer,
+            sample_rate,
+            _stream: stream,
+        })
+    }
+}
+
+impl AudioSink for DeviceSink {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn write(&mut self, samples: &[f32]) -> io::Result<()> {
+        let max = (self.sample_rate as f32 * MAX_BUFFERED) as usize;
+        let mut buffer = self.buffer.lock().unwrap();
+
+        buffer.extend(samples);
+        while buffer.len() > max {
+            buffer.pop_front();
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds output stream playing buffered mono samples on every channel
+fn build_stream<T: cpal::Sample>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    buffer: &Arc<Mutex<VecDeque<f32>>>,
+) -> Result<cpal::Stream, String> {
+    let channels = config.channels as usize;
+    let buffer = Arc::clone(buffer);
+
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                let mut buffer = buffer.lock().unwrap();
+
+                for frame in data.chunks_mut(channels) {
+                    let sample = buffer.pop_front().unwrap_or(0.0);
+                    for out in frame.iter_mut() {
+                        *out = cpal::Sample::from(&sample);
+                    }
+                }
+            },
+            |err| eprintln!("error: audio stream failed: {}", err),
+        )
+        .map_err(|err| err.to_string())
+}
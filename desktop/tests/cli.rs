@@ -0,0 +1,109 @@
+//! Command line tests: options and their values, and the errors for anything malformed.
+
+use std::path::PathBuf;
+
+use chip8::cli::{self, Command, Options};
+use chip8_core::{audio::Waveform, clock::DEFAULT_IPS, quirks::Quirks};
+
+fn parse(args: &[&str]) -> Result<Command, String> {
+    cli::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+/// Parses arguments which must run a ROM
+fn options(args: &[&str]) -> Options {
+    match parse(args) {
+        Ok(Command::Run(options)) => *options,
+        Ok(Command::Help) => panic!("{:?}: help", args),
+        Err(err) => panic!("{:?}: {}", args, err),
+    }
+}
+
+#[test]
+fn defaults() {
+    let options = options(&["pong.ch8"]);
+    assert_eq!(options.rom, PathBuf::from("pong.ch8"));
+    assert_eq!(options.ips, DEFAULT_IPS);
+    assert_eq!(options.quirks, Quirks::cowgod());
+    assert_eq!(options.capture_scale, 4);
+    assert!(options.trace_ranges.is_empty());
+    assert!(!options.headless && !options.paused && !options.debug);
+}
+
+#[test]
+fn valid_options() {
+    // --speed counts instructions per frame
+    assert_eq!(options(&["--speed", "15", "a.ch8"]).ips, 900);
+    assert_eq!(options(&["--ips", "15", "a.ch8"]).ips, 15);
+
+    let options = options(&[
+        "--quirks",
+        "vip",
+        "--trace-range",
+        "0x200-0x2ff",
+        "--trace-range",
+        "600-600",
+        "pong.ch8",
+        "--seed",
+        "42",
+        "--waveform",
+        "sine",
+        "--volume",
+        "1",
+        "--headless",
+        "--frames",
+        "120",
+    ]);
+    assert_eq!(options.rom, PathBuf::from("pong.ch8"));
+    assert_eq!(options.quirks, Quirks::cosmac_vip());
+    assert_eq!(options.trace_ranges, vec![(0x200, 0x2FF), (600, 600)]);
+    assert_eq!(options.seed, Some(42));
+    assert_eq!(options.beeper.waveform, Waveform::Sine);
+    assert_eq!(options.beeper.volume, 1.0);
+    assert!(options.headless);
+    assert_eq!(options.frames, Some(120));
+
+    assert!(matches!(parse(&["a.ch8", "-h"]), Ok(Command::Help)));
+    assert!(matches!(parse(&["--help"]), Ok(Command::Help)));
+}
+
+#[test]
+fn errors() {
+    let cases: &[(&[&str], &str)] = &[
+        (&[], "no ROM given"),
+        (&["a.ch8", "b.ch8"], "unexpected argument 'b.ch8'"),
+        (&["--fast", "a.ch8"], "unknown option '--fast'"),
+        (&["a.ch8", "--ips"], "missing value for --ips"),
+        (&["--ips", "0", "a.ch8"], "invalid value '0' for --ips"),
+        (&["--speed", "x", "a.ch8"], "invalid value 'x' for --speed"),
+        (
+            &["--quirks", "eti", "a.ch8"],
+            "unknown quirks profile 'eti', expected one of: cowgod, vip, chip48, schip, xochip",
+        ),
+        (
+            &["--trace-range", "0x300-0x200", "a.ch8"],
+            "invalid value '0x300-0x200' for --trace-range",
+        ),
+        (
+            &["--trace-range", "0x200", "a.ch8"],
+            "invalid value '0x200' for --trace-range",
+        ),
+        (
+            &["--volume", "2", "a.ch8"],
+            "invalid value '2' for --volume",
+        ),
+        (
+            &["--capture-scale", "65", "a.ch8"],
+            "invalid value '65' for --capture-scale",
+        ),
+        (
+            &["--play", "a.movie", "--input", "a.txt", "a.ch8"],
+            "--play and --input cannot be used together",
+        ),
+    ];
+    for (args, err) in cases {
+        match parse(args) {
+            Err(message) => assert_eq!(message, *err, "{:?}", args),
+            Ok(_) => panic!("{:?}: parsed", args),
+        }
+    }
+}
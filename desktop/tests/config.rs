@@ -0,0 +1,127 @@
+//! Config file tests: settings, themes and key bindings as parsed, and errors naming the line
+//! they come from.
+
+use chip8::{config::Config, keymap::Hotkey};
+use chip8_core::theme::Theme;
+use winit::event::VirtualKeyCode;
+
+const CONFIG: &str = "\
+# Comments and blank lines are skipped
+theme = mine
+
+[theme mine]
+background = #101820
+foreground = #F2AA4C
+plane2 = #FF0000
+
+[keys]
+1 = Up
+  a = Z, numpad0
+f =
+
+[keys space invaders.ch8]
+5 = Space
+
+[hotkeys]
+pause = P, F1
+";
+
+#[test]
+fn valid_config() {
+    let config = Config::parse(CONFIG).unwrap();
+    assert_eq!(config.theme.as_deref(), Some("mine"));
+
+    // The colour left out is derived from the others
+    let derived = Theme::from_colours("mine", [0x10, 0x18, 0x20], [0xF2, 0xAA, 0x4C]);
+    assert_eq!(config.themes.len(), 1);
+    assert_eq!(config.themes[0].name, "mine");
+    assert_eq!(
+        config.themes[0].palette,
+        [
+            [0x10, 0x18, 0x20],
+            [0xF2, 0xAA, 0x4C],
+            [0xFF, 0x00, 0x00],
+            derived.palette[3]
+        ]
+    );
+
+    let keys: Vec<(Option<&str>, u8, &[VirtualKeyCode])> = config
+        .keys
+        .iter()
+        .map(|binding| (binding.rom.as_deref(), binding.code, &binding.keys[..]))
+        .collect();
+    assert_eq!(
+        keys,
+        vec![
+            (None, 0x1, &[VirtualKeyCode::Up][..]),
+            (None, 0xA, &[VirtualKeyCode::Z, VirtualKeyCode::Numpad0][..]),
+            (None, 0xF, &[][..]),
+            (
+                Some("space invaders.ch8"),
+                0x5,
+                &[VirtualKeyCode::Space][..]
+            ),
+        ]
+    );
+
+    assert_eq!(
+        config.hotkeys,
+        vec![(Hotkey::Pause, vec![VirtualKeyCode::P, VirtualKeyCode::F1])]
+    );
+}
+
+#[test]
+fn redefined_theme() {
+    let config = Config::parse(
+        "[theme a]\nbackground = #000000\nforeground = #FFFFFF\n\
+         [theme a]\nbackground = #111111\nforeground = #EEEEEE\n",
+    )
+    .unwrap();
+    assert_eq!(config.themes.len(), 1);
+    assert_eq!(config.themes[0].palette[0], [0x11; 3]);
+}
+
+#[test]
+fn errors() {
+    for (text, err) in [
+        ("volume = 3", "unknown setting 'volume' on line 1"),
+        ("\n\n[keys", "unterminated section '[keys' on line 3"),
+        ("[colours]", "unknown section '[colours]' on line 1"),
+        (
+            "[keys]\n1 Up",
+            "expected 'key = value', found '1 Up' on line 2",
+        ),
+        ("[keys]\n1 = Up, Foo", "unknown key 'Foo' on line 2"),
+        ("[keys]\n10 = Up", "invalid CHIP-8 key '10' on line 2"),
+        ("[keys]\ng = Up", "invalid CHIP-8 key 'g' on line 2"),
+        ("[hotkeys]\nquit = Q", "unknown hotkey 'quit' on line 2"),
+        ("[hotkeys]\npause = Pause", "unknown key 'Pause' on line 2"),
+        (
+            "[theme a]\nbackground = #12345",
+            "invalid colour '#12345' on line 2",
+        ),
+        (
+            "[theme a]\nborder = #123456",
+            "unknown theme colour 'border' on line 2",
+        ),
+    ] {
+        assert_eq!(Config::parse(text).unwrap_err(), err, "{}", text);
+    }
+}
+
+#[test]
+fn incomplete_themes() {
+    // Reported on the theme's own header, whether another section or the end of the file
+    // comes next
+    let text = "theme = b\n\n[theme b]\nbackground = #000000\n\n[keys]\n1 = Up\n";
+    assert_eq!(
+        Config::parse(text).unwrap_err(),
+        "theme 'b' needs a background and foreground on line 3"
+    );
+
+    let text = "[theme a]\nbackground = #000000\nforeground = #FFFFFF\n[theme b]\n";
+    assert_eq!(
+        Config::parse(text).unwrap_err(),
+        "theme 'b' needs a background and foreground on line 4"
+    );
+}
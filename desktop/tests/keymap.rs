@@ -0,0 +1,121 @@
+//! Key binding tests: defaults, overrides from the config file for all ROMs or just one,
+//! key names, and keys bound to both the keypad and a hotkey being refused.
+
+use std::path::Path;
+
+use chip8::{
+    config::Config,
+    keymap::{self, Hotkey, KeyMap},
+};
+use winit::event::VirtualKeyCode;
+
+/// Key map for `rom` with given config file, which must be valid
+fn keymap(config: &str, rom: &str) -> Result<KeyMap, String> {
+    KeyMap::new(&Config::parse(config).unwrap(), Path::new(rom))
+}
+
+#[test]
+fn defaults() {
+    let keymap = keymap("", "pong.ch8").unwrap();
+    assert_eq!(keymap.keypad(VirtualKeyCode::Key1), Some(0x1));
+    assert_eq!(keymap.keypad(VirtualKeyCode::W), Some(0x5));
+    assert_eq!(keymap.keypad(VirtualKeyCode::V), Some(0xF));
+    assert_eq!(keymap.keypad(VirtualKeyCode::Space), None);
+
+    assert_eq!(keymap.hotkey(VirtualKeyCode::Space), Some(Hotkey::Pause));
+    assert_eq!(keymap.hotkey(VirtualKeyCode::W), None);
+    assert_eq!(keymap.hotkey_name(Hotkey::Record), "F11");
+
+    for (hotkey, name, _) in &Hotkey::ALL {
+        assert_eq!(Hotkey::from_name(name), Some(*hotkey));
+        assert_eq!(hotkey.name(), *name);
+    }
+}
+
+#[test]
+fn overrides() {
+    let config = "\
+[keys]
+5 = Up, W
+6 =
+
+[keys pong.ch8]
+5 = K
+
+[hotkeys]
+pause = P, Return
+record =
+";
+
+    // Bindings replace all default keys of what they bind
+    let other = keymap(config, "roms/tetris.ch8").unwrap();
+    assert_eq!(other.keypad(VirtualKeyCode::Up), Some(0x5));
+    assert_eq!(other.keypad(VirtualKeyCode::W), Some(0x5));
+    assert_eq!(other.keypad(VirtualKeyCode::E), None);
+    assert_eq!(other.hotkey(VirtualKeyCode::Space), None);
+    assert_eq!(other.hotkey(VirtualKeyCode::P), Some(Hotkey::Pause));
+    assert_eq!(other.hotkey_name(Hotkey::Pause), "P");
+    assert_eq!(other.hotkey_name(Hotkey::Record), "(unbound)");
+
+    // The ROM's own bindings come last, matched on its file name
+    let pong = keymap(config, "roms/pong.ch8").unwrap();
+    assert_eq!(pong.keypad(VirtualKeyCode::K), Some(0x5));
+    assert_eq!(pong.keypad(VirtualKeyCode::Up), None);
+    assert_eq!(pong.keypad(VirtualKeyCode::W), None);
+}
+
+#[test]
+fn conflicting_bindings() {
+    let err = |config: &str, rom: &str| keymap(config, rom).err();
+
+    assert_eq!(
+        err("[keys]\n5 = Up, Space", "pong.ch8"),
+        Some(String::from(
+            "key Space is bound to both CHIP-8 key 5 and hotkey 'pause'"
+        ))
+    );
+    // A hotkey moved onto a default keypad key
+    assert_eq!(
+        err("[hotkeys]\nreset = Q", "pong.ch8"),
+        Some(String::from(
+            "key Q is bound to both CHIP-8 key 4 and hotkey 'reset'"
+        ))
+    );
+
+    // Only the ROM being run counts
+    let config = "[keys pong.ch8]\nb = F5";
+    assert_eq!(
+        err(config, "pong.ch8"),
+        Some(String::from(
+            "key F5 is bound to both CHIP-8 key B and hotkey 'save'"
+        ))
+    );
+    assert_eq!(err(config, "tetris.ch8"), None);
+
+    // Moving the hotkey away or unbinding it resolves the conflict
+    let keymap = keymap("[keys]\n5 = Space\n[hotkeys]\npause = P", "pong.ch8").unwrap();
+    assert_eq!(keymap.keypad(VirtualKeyCode::Space), Some(0x5));
+    assert_eq!(keymap.hotkey(VirtualKeyCode::Space), None);
+    assert_eq!(err("[keys]\nb = F5\n[hotkeys]\nsave =", "pong.ch8"), None);
+}
+
+#[test]
+fn key_names() {
+    for (name, key) in [
+        ("q", VirtualKeyCode::Q),
+        ("Q", VirtualKeyCode::Q),
+        ("7", VirtualKeyCode::Key7),
+        ("F12", VirtualKeyCode::F12),
+        ("Numpad3", VirtualKeyCode::Numpad3),
+        ("space", VirtualKeyCode::Space),
+        ("Backspace", VirtualKeyCode::Back),
+        ("Enter", VirtualKeyCode::Return),
+        ("LBracket", VirtualKeyCode::LBracket),
+    ] {
+        assert_eq!(keymap::key_from_name(name), Some(key), "{}", name);
+    }
+
+    for name in ["", "Key7", "F13", "Foo", "ctrl"] {
+        assert_eq!(keymap::key_from_name(name), None, "{}", name);
+    }
+}